@@ -0,0 +1,204 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::elementary::{ElementaryParseError, ElementaryRule};
+use crate::grid::{CellState, Grid, Transition, EMPTY};
+use crate::lenia::{LeniaParseError, LeniaRule};
+use crate::neighbourhood::Neighbourhood;
+use crate::rule::{Rule, RuleParseError};
+use crate::ruletable::{self, RuleTable, TableNeighbourhood};
+
+/// Any rule the engine can run.
+#[derive(Debug, Clone)]
+pub enum Automaton {
+    LifeLike(Rule),
+    Table(RuleTable),
+    Elementary(ElementaryRule),
+    /// Continuous; runs on a [`Field`](crate::lenia::Field) rather than a grid.
+    Lenia(LeniaRule),
+}
+
+impl Automaton {
+    /// Number of cell states including empty.
+    pub fn states(&self) -> u8 {
+        match self {
+            Automaton::LifeLike(rule) => rule.states(),
+            Automaton::Table(table) => table.states(),
+            Automaton::Elementary(rule) => rule.states(),
+            Automaton::Lenia(_) => 2,
+        }
+    }
+
+    /// Whether cells hold continuous values instead of discrete states.
+    pub fn is_continuous(&self) -> bool {
+        matches!(self, Automaton::Lenia(_))
+    }
+
+    /// Whether an empty cell with nothing around it comes alive, as under B0
+    /// rules, so empty parts of a board change too.
+    pub fn is_born_from_nothing(&self) -> bool {
+        self.next_states(&Grid::new(1, 1))[0] != EMPTY
+    }
+
+    /// Whether the board should be read as hexagonal.
+    pub fn is_hexagonal(&self) -> bool {
+        match self {
+            Automaton::LifeLike(rule) => matches!(rule.neighbourhood(), Neighbourhood::Hexagonal(_)),
+            Automaton::Table(table) => table.neighbourhood() == TableNeighbourhood::Hexagonal,
+            Automaton::Elementary(_) | Automaton::Lenia(_) => false,
+        }
+    }
+
+    /// How many cells away a cell's next state can depend on, or `None` if
+    /// the rule does not work cell by cell: 1D rules push the whole board up
+    /// every generation and Lenia runs on its own field.
+    pub fn reach(&self) -> Option<u32> {
+        match self {
+            Automaton::LifeLike(rule) => Some(rule.neighbourhood().range() as u32),
+            Automaton::Table(_) => Some(1),
+            Automaton::Elementary(_) | Automaton::Lenia(_) => None,
+        }
+    }
+
+    /// RGB colour of every state. Life-like rules are green when alive and
+    /// fade from orange towards black through their dying states.
+    pub fn colours(&self) -> Vec<[u8; 3]> {
+        match self {
+            Automaton::LifeLike(rule) => {
+                let states = rule.states();
+                let mut colours = vec![[26, 25, 32], [153, 229, 80]];
+                colours.extend((2..states).map(|state| {
+                    let fade = (state - 1) as f32 / (states - 1) as f32;
+                    let shade = |c: f32| (c * (1.0 - 0.8 * fade)) as u8;
+                    [shade(255.0), shade(131.0), shade(0.0)]
+                }));
+                colours
+            }
+            Automaton::Table(table) => table.colours().to_vec(),
+            Automaton::Elementary(rule) if rule.states() > 2 => ruletable::default_colours(rule.states()),
+            Automaton::Elementary(_) | Automaton::Lenia(_) => vec![[26, 25, 32], [153, 229, 80]],
+        }
+    }
+}
+
+impl Default for Automaton {
+    fn default() -> Self {
+        Automaton::LifeLike(Rule::default())
+    }
+}
+
+impl From<Rule> for Automaton {
+    fn from(rule: Rule) -> Self {
+        Automaton::LifeLike(rule)
+    }
+}
+
+impl From<RuleTable> for Automaton {
+    fn from(table: RuleTable) -> Self {
+        Automaton::Table(table)
+    }
+}
+
+impl From<ElementaryRule> for Automaton {
+    fn from(rule: ElementaryRule) -> Self {
+        Automaton::Elementary(rule)
+    }
+}
+
+impl From<LeniaRule> for Automaton {
+    fn from(rule: LeniaRule) -> Self {
+        Automaton::Lenia(rule)
+    }
+}
+
+/// Continuous rules leave the grid as it is; step their field instead.
+impl Transition for Automaton {
+    fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        match self {
+            Automaton::LifeLike(rule) => rule.next_states(grid),
+            Automaton::Table(table) => table.next_states(grid),
+            Automaton::Elementary(rule) => rule.next_states(grid),
+            Automaton::Lenia(_) => grid.iter().map(|(_, _, state)| state).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Automaton::LifeLike(rule) => rule.fmt(f),
+            Automaton::Table(table) => table.fmt(f),
+            Automaton::Elementary(rule) => rule.fmt(f),
+            Automaton::Lenia(rule) => rule.fmt(f),
+        }
+    }
+}
+
+impl FromStr for Automaton {
+    type Err = AutomatonParseError;
+
+    /// Reads a one-dimensional rule such as `W110` or `T777K3`, a Lenia rule
+    /// such as `Lenia:R13,mu0.15`, or else any rulestring [`Rule`] accepts.
+    /// Rule tables come from files instead; see [`RuleTable::load`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars().map(|c| c.to_ascii_uppercase());
+        if s.get(..5).is_some_and(|prefix| prefix.eq_ignore_ascii_case("lenia")) {
+            Ok(Automaton::Lenia(s.parse()?))
+        } else if matches!(chars.next(), Some('W' | 'T')) && chars.next().is_some_and(|c| c.is_ascii_digit()) {
+            Ok(Automaton::Elementary(s.parse()?))
+        } else {
+            Ok(Automaton::LifeLike(s.parse()?))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutomatonParseError {
+    Rule(RuleParseError),
+    Elementary(ElementaryParseError),
+    Lenia(LeniaParseError),
+}
+
+impl From<RuleParseError> for AutomatonParseError {
+    fn from(e: RuleParseError) -> Self {
+        AutomatonParseError::Rule(e)
+    }
+}
+
+impl From<ElementaryParseError> for AutomatonParseError {
+    fn from(e: ElementaryParseError) -> Self {
+        AutomatonParseError::Elementary(e)
+    }
+}
+
+impl From<LeniaParseError> for AutomatonParseError {
+    fn from(e: LeniaParseError) -> Self {
+        AutomatonParseError::Lenia(e)
+    }
+}
+
+impl fmt::Display for AutomatonParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutomatonParseError::Rule(e) => e.fmt(f),
+            AutomatonParseError::Elementary(e) => e.fmt(f),
+            AutomatonParseError::Lenia(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AutomatonParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_rules_born_from_nothing() {
+        for (rule, born) in [("B3/S23", false), ("B0/S8", true), ("B012345678/S", true), ("B2/S/C3", false)] {
+            let rule: Automaton = rule.parse().unwrap();
+            assert_eq!(rule.is_born_from_nothing(), born, "{rule}");
+        }
+        assert!(!Automaton::Lenia(LeniaRule::orbium()).is_born_from_nothing());
+    }
+}
@@ -0,0 +1,278 @@
+//! A bit-parallel stepper for two-state Life-like rules: cells are packed 64
+//! to a word and every word's neighbour counts are added up with full adders
+//! at once, instead of visiting cells one by one.
+
+use crate::grid::{CellState, Grid, ALIVE, EMPTY};
+use crate::neighbourhood::Neighbourhood;
+use crate::rule::Rule;
+
+/// The birth and survival counts of a rule the bit stepper can run, as bit
+/// masks over neighbour counts 0 to 8.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitRule {
+    birth: u16,
+    survival: u16,
+}
+
+impl BitRule {
+    /// The bit form of `rule`, or `None` unless it is a two-state, outer
+    /// totalistic rule on the range 1 Moore neighbourhood.
+    pub fn new(rule: &Rule) -> Option<Self> {
+        let fits = rule.states() == 2
+            && *rule.neighbourhood() == Neighbourhood::Moore(1)
+            && !rule.includes_centre()
+            && !rule.is_isotropic();
+        if !fits {
+            return None;
+        }
+        let mask = |test: &dyn Fn(u16) -> bool| (0..=8).filter(|&n| test(n)).fold(0, |mask, n| mask | 1 << n);
+        Some(Self {
+            birth: mask(&|n| rule.is_birth(n)),
+            survival: mask(&|n| rule.is_survival(n)),
+        })
+    }
+
+    /// The state of every cell on `grid` one generation on, row by row.
+    pub fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        let board = BitBoard::pack(grid);
+        let next = board.step(self);
+        let width = grid.width() as usize;
+        let mut states = vec![EMPTY; width * grid.height() as usize];
+        for (y, row) in states.chunks_exact_mut(width).enumerate() {
+            let bits = &next[(y + 1) * board.words..(y + 2) * board.words];
+            // 64 cells at a time, skipping the border bit at the start
+            for (j, cells) in row.chunks_mut(64).enumerate() {
+                let word = bits[j] >> 1 | bits.get(j + 1).map_or(0, |next| next << 63);
+                for (i, eight) in cells.chunks_mut(8).enumerate() {
+                    let bytes = SPREAD[(word >> (8 * i)) as u8 as usize].to_le_bytes();
+                    eight.copy_from_slice(&bytes[..eight.len()]);
+                }
+            }
+        }
+        states
+    }
+
+    /// Next state of 64 cells at once, given their current state and the bits
+    /// of their neighbour counts.
+    fn apply(&self, alive: u64, [b0, b1, b2, b3]: [u64; 4]) -> u64 {
+        // which cells have each value of the low and high two count bits
+        let low = [!b1 & !b0, !b1 & b0, b1 & !b0, b1 & b0];
+        let high = [!b3 & !b2, !b3 & b2, b3 & !b2];
+        let (mut born, mut survive) = (0, 0);
+        for n in 0..9 {
+            let equal = high[n >> 2] & low[n & 3];
+            born |= equal & 0u64.wrapping_sub((self.birth >> n & 1) as u64);
+            survive |= equal & 0u64.wrapping_sub((self.survival >> n & 1) as u64);
+        }
+        alive & survive | !alive & born
+    }
+}
+
+/// A grid's live cells packed into rows of words, with a border of one cell
+/// all round filled in through the grid's topology. Bit `i` of a row is the
+/// cell at `x = i - 1`, and row `j` is `y = j - 1`.
+struct BitBoard {
+    words: usize,
+    rows: usize,
+    bits: Vec<u64>,
+}
+
+impl BitBoard {
+    fn pack(grid: &Grid) -> Self {
+        let (w, h) = (grid.width(), grid.height());
+        let words = (w as usize + 2).div_ceil(64);
+        let rows = h as usize + 2;
+        let mut board = Self {
+            words,
+            rows,
+            bits: vec![0; words * rows],
+        };
+        for y in 0..h {
+            let bits = &mut board.bits[(y as usize + 1) * words..(y as usize + 2) * words];
+            for (j, cells) in grid.row(y).chunks(64).enumerate() {
+                let word = cells
+                    .chunks(8)
+                    .enumerate()
+                    .fold(0u64, |word, (i, eight)| word | (gather(eight) as u64) << (8 * i));
+                bits[j] |= word << 1;
+                if let Some(next) = bits.get_mut(j + 1) {
+                    *next |= word >> 63;
+                }
+            }
+        }
+        let sides = (-1..=h).flat_map(|y| [(-1, y), (w, y)]);
+        let ends = (0..w).flat_map(|x| [(x, -1), (x, h)]);
+        for (x, y) in sides.chain(ends) {
+            if grid.get(x, y) == ALIVE {
+                board.set(x + 1, y + 1);
+            }
+        }
+        board
+    }
+
+    fn set(&mut self, x: i32, y: i32) {
+        let (x, y) = (x as usize, y as usize);
+        self.bits[y * self.words + x / 64] |= 1 << (x % 64);
+    }
+
+    /// The next generation of every row but the border ones, which are left
+    /// empty.
+    fn step(&self, rule: &BitRule) -> Vec<u64> {
+        let mut next = vec![0; self.bits.len()];
+        for y in 1..self.rows - 1 {
+            let row = |y: usize| &self.bits[y * self.words..(y + 1) * self.words];
+            let (up, mid, down) = (row(y - 1), row(y), row(y + 1));
+            let out = &mut next[y * self.words..(y + 1) * self.words];
+            for (k, word) in out.iter_mut().enumerate() {
+                let (nw, n, ne) = shifted(up, k);
+                let (w, centre, e) = shifted(mid, k);
+                let (sw, s, se) = shifted(down, k);
+                *word = rule.apply(centre, count([nw, n, ne, w, e, sw, s, se]));
+            }
+        }
+        next
+    }
+}
+
+/// Eight cell states, one to a byte, for every byte of bits: byte `b` of
+/// entry `i` is bit `b` of `i`.
+const SPREAD: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut b = 0;
+        while b < 8 {
+            table[i] |= ((i as u64 >> b) & 1) << (8 * b);
+            b += 1;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Up to eight cells gathered into the bits of a byte, set where a cell is
+/// alive. Each byte is compared all at once: those equal to [`ALIVE`] are
+/// cleared, the top bit marks the ones left zero, and a multiplication
+/// brings the marks together.
+fn gather(cells: &[CellState]) -> u8 {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const LOW: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+    let mut bytes = [EMPTY; 8];
+    bytes[..cells.len()].copy_from_slice(cells);
+    let other = u64::from_le_bytes(bytes) ^ (ONES * ALIVE as u64);
+    let alive = !(((other & LOW) + LOW) | other) & !LOW;
+    ((alive >> 7).wrapping_mul(0x0102_0408_1020_4080) >> 56) as u8
+}
+
+/// Word `k` of `row` shifted so each bit holds the cell's west neighbour,
+/// then the word itself, then shifted for the east neighbour.
+fn shifted(row: &[u64], k: usize) -> (u64, u64, u64) {
+    let word = row[k];
+    let from_left = if k > 0 { row[k - 1] >> 63 } else { 0 };
+    let from_right = if k + 1 < row.len() { row[k + 1] << 63 } else { 0 };
+    (word << 1 | from_left, word, word >> 1 | from_right)
+}
+
+/// Adds up eight one-bit numbers in every bit position, giving the four bits
+/// of each sum, lowest first.
+fn count(n: [u64; 8]) -> [u64; 4] {
+    let full_add = |a: u64, b: u64, c: u64| (a ^ b ^ c, a & b | c & (a ^ b));
+    let half_add = |a: u64, b: u64| (a ^ b, a & b);
+
+    let (sum_a, carry_a) = full_add(n[0], n[1], n[2]);
+    let (sum_b, carry_b) = full_add(n[3], n[4], n[5]);
+    let (sum_c, carry_c) = half_add(n[6], n[7]);
+    let (ones, carry_d) = full_add(sum_a, sum_b, sum_c);
+
+    let (sum_e, carry_e) = full_add(carry_a, carry_b, carry_c);
+    let (twos, carry_f) = half_add(sum_e, carry_d);
+    let (fours, eights) = half_add(carry_e, carry_f);
+    [ones, twos, fours, eights]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{soup, Transition};
+    use crate::topology::Topology;
+
+    /// The per-cell stepper that the bit stepper has to agree with.
+    fn reference(rule: &Rule, grid: &Grid) -> Vec<CellState> {
+        let keys = rule.neighbour_keys(grid);
+        grid.iter().zip(keys).map(|((_, _, state), key)| rule.next_state(state, key)).collect()
+    }
+
+    #[test]
+    fn matches_reference_stepper() {
+        let rules = ["B3/S23", "B36/S23", "B3678/S34678", "B0/S8", "B012345678/S012345678", "B1/S"];
+        let sizes = [(1, 1), (3, 7), (63, 5), (64, 64), (65, 3), (130, 40)];
+        for rule in rules {
+            let rule: Rule = rule.parse().unwrap();
+            let bits = BitRule::new(&rule).unwrap();
+            for (i, &(width, height)) in sizes.iter().enumerate() {
+                for topology in Topology::ALL {
+                    let mut grid = soup(width, height, i as u64, 2).with_topology(topology);
+                    for generation in 0..8 {
+                        let expected = reference(&rule, &grid);
+                        assert_eq!(
+                            bits.next_states(&grid),
+                            expected,
+                            "{rule} on {width}x{height} {topology:?}, generation {generation}"
+                        );
+                        grid.step(&rule);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rule_steps_with_bits_where_it_can() {
+        let life = Rule::life();
+        let bits = life.bit_rule().expect("Life should be stepped with bits");
+        assert_eq!(bits, BitRule { birth: 1 << 3, survival: 1 << 2 | 1 << 3 });
+        let grid = soup(200, 100, 7, 2);
+        assert_eq!(life.next_states(&grid), bits.next_states(&grid));
+        assert_eq!(life.next_states(&grid), reference(&life, &grid));
+    }
+
+    #[test]
+    fn packs_only_live_cells() {
+        assert_eq!(gather(&[1, 0, 1, 1, 0, 0, 0, 1]), 0b1000_1101);
+        assert_eq!(gather(&[2, 1, 255, 0x81, 0x01, 3]), 0b1_0010);
+        assert_eq!(gather(&[1; 3]), 0b111);
+        for i in 0..=255u8 {
+            let cells = SPREAD[i as usize].to_le_bytes();
+            assert_eq!(gather(&cells), i);
+        }
+    }
+
+    #[test]
+    fn declines_rules_it_cannot_run() {
+        for rule in ["B2/S/C3", "B2ce3ai/S23-a", "B2/S34H", "B1/S1V", "R2,C0,M0,S2..3,B3..3,NM"] {
+            let rule: Rule = rule.parse().unwrap();
+            assert_eq!(rule.bit_rule(), None, "{rule}");
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored` to see the speed-up.
+    #[test]
+    #[ignore = "timing, only meaningful in release builds"]
+    fn is_an_order_of_magnitude_faster() {
+        use std::time::Instant;
+        let life = Rule::life();
+        let bits = life.bit_rule().unwrap();
+        let grid = soup(1024, 1024, 3, 2);
+        let time = |step: &dyn Fn(&Grid) -> Vec<CellState>| {
+            let start = Instant::now();
+            for _ in 0..10 {
+                std::hint::black_box(step(&grid));
+            }
+            start.elapsed()
+        };
+        let slow = time(&|grid| reference(&life, grid));
+        let fast = time(&|grid| bits.next_states(grid));
+        println!("reference {slow:?}, bits {fast:?}");
+        assert!(fast * 10 <= slow, "reference {slow:?}, bits {fast:?}");
+    }
+}
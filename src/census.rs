@@ -0,0 +1,340 @@
+//! Counting the objects on a settled board. The board is split into islands
+//! of cells close enough to interact, each island is run on its own to find
+//! how it behaves, and islands that are the same object in any orientation
+//! or phase are counted together, by name where the object is a known one.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use crate::grid::{CellState, Transition, ALIVE};
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+/// Generations an island is run for to see it come back.
+pub const MAX_PERIOD: u64 = 256;
+
+/// Well-known Life objects, each in one phase, with `o` for a live cell.
+const LIFE_OBJECTS: &[(&str, &[&str])] = &[
+    ("block", &["oo", "oo"]),
+    ("beehive", &[".oo.", "o..o", ".oo."]),
+    ("loaf", &[".oo.", "o..o", ".o.o", "..o."]),
+    ("boat", &["oo.", "o.o", ".o."]),
+    ("ship", &["oo.", "o.o", ".oo"]),
+    ("tub", &[".o.", "o.o", ".o."]),
+    ("pond", &[".oo.", "o..o", "o..o", ".oo."]),
+    ("long boat", &["oo..", "o.o.", ".o.o", "..o."]),
+    ("barge", &[".o..", "o.o.", ".o.o", "..o."]),
+    ("mango", &[".oo..", "o..o.", ".o..o", "..oo."]),
+    ("eater 1", &["oo..", "o.o.", "..o.", "..oo"]),
+    ("snake", &["oo.o", "o.oo"]),
+    ("aircraft carrier", &["oo..", "o..o", "..oo"]),
+    ("blinker", &["ooo"]),
+    ("toad", &[".ooo", "ooo."]),
+    ("beacon", &["oo..", "oo..", "..oo", "..oo"]),
+    ("clock", &["..o.", "o.o.", ".o.o", ".o.."]),
+    ("pentadecathlon", &["..o....o..", "oo.oooo.oo", "..o....o.."]),
+    ("glider", &[".o.", "..o", "ooo"]),
+    ("lightweight spaceship", &[".o..o", "o....", "o...o", "oooo."]),
+    ("middleweight spaceship", &["...o..", ".o...o", "o.....", "o....o", "ooooo."]),
+    ("heavyweight spaceship", &["...oo..", ".o....o", "o......", "o.....o", "oooooo."]),
+];
+
+/// An island's cells moved to touch the origin, sorted.
+type Shape = Vec<(i64, i64, CellState)>;
+
+/// How an island behaves when run on its own.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Still,
+    Oscillator { period: u64 },
+    /// Comes back `(dx, dy)` cells away every `period` generations.
+    Spaceship { period: u64, dx: i64, dy: i64 },
+    /// Did not come back within [`MAX_PERIOD`] generations, or died.
+    Unsettled,
+}
+
+impl Kind {
+    pub fn period(&self) -> Option<u64> {
+        match *self {
+            Kind::Still => Some(1),
+            Kind::Oscillator { period } | Kind::Spaceship { period, .. } => Some(period),
+            Kind::Unsettled => None,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Kind::Still => write!(f, "still life"),
+            Kind::Oscillator { period } => write!(f, "p{period} oscillator"),
+            Kind::Spaceship { period, dx, dy } => {
+                let distance = dx.unsigned_abs().max(dy.unsigned_abs());
+                let divisor = gcd(distance, period);
+                match (distance / divisor, period / divisor) {
+                    (1, period) => write!(f, "c/{period} spaceship"),
+                    (distance, period) => write!(f, "{distance}c/{period} spaceship"),
+                }
+            }
+            Kind::Unsettled => write!(f, "unsettled"),
+        }
+    }
+}
+
+/// Names of known objects, keyed by their shape in every orientation and
+/// phase.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+    names: HashMap<Shape, &'static str>,
+}
+
+impl Library {
+    /// The common still lifes, oscillators and spaceships of Conway's Life.
+    pub fn life() -> Self {
+        let life = Rule::life();
+        let mut names = HashMap::new();
+        for &(name, rows) in LIFE_OBJECTS {
+            let cells = rows.iter().enumerate().flat_map(|(y, row)| {
+                row.bytes()
+                    .enumerate()
+                    .filter(|&(_, c)| c == b'o')
+                    .map(move |(x, _)| (x as i64, -(y as i64), ALIVE))
+            });
+            let (key, _) = classify(cells.collect(), &life, 1);
+            names.insert(key, name);
+        }
+        Self { names }
+    }
+}
+
+/// One kind of object and how many of it there are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The library's name for the object, if it has one.
+    pub name: Option<&'static str>,
+    pub kind: Kind,
+    /// Cells in the phase the object is known by.
+    pub cells: usize,
+    pub count: usize,
+}
+
+impl Entry {
+    /// The object's name, or a description for one the library lacks.
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(name) => name.to_string(),
+            None => format!("{}-cell object", self.cells),
+        }
+    }
+}
+
+/// The objects on a board, most common first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Census {
+    pub entries: Vec<Entry>,
+}
+
+impl Census {
+    /// Counts the objects in `universe` under `rule`, which must only look
+    /// `reach` cells away. Cells within `2 * reach` of each other can affect
+    /// the same cell, so they belong to the same island; spaceships need this,
+    /// as some of their phases have cells two apart.
+    pub fn take(universe: &Universe, rule: &impl Transition, reach: u32, library: &Library) -> Self {
+        let mut counts: HashMap<Shape, Entry> = HashMap::new();
+        for island in islands(universe, 2 * reach as i64) {
+            let (key, kind) = classify(island, rule, reach);
+            counts
+                .entry(key)
+                .or_insert_with_key(|key| Entry {
+                    name: library.names.get(key).copied(),
+                    kind,
+                    cells: key.len(),
+                    count: 0,
+                })
+                .count += 1;
+        }
+        let mut entries: Vec<_> = counts.into_values().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label().cmp(&b.label())));
+        Self { entries }
+    }
+
+    /// The census as comma-separated values with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("object,kind,period,cells,count\n");
+        for entry in &self.entries {
+            let period = entry.kind.period().map_or(String::new(), |period| period.to_string());
+            csv += &format!("{},{},{},{},{}\n", entry.label(), entry.kind, period, entry.cells, entry.count);
+        }
+        csv
+    }
+}
+
+/// The non-empty cells of `universe`, split into groups where every cell is
+/// within `distance` cells of another in the group.
+fn islands(universe: &Universe, distance: i64) -> Vec<Vec<(i64, i64, CellState)>> {
+    let cells: HashMap<(i64, i64), CellState> = universe.iter().map(|(x, y, state)| ((x, y), state)).collect();
+    let mut seen = HashSet::new();
+    let mut islands = vec![];
+    for &start in cells.keys() {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut island = vec![];
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            island.push((x, y, cells[&(x, y)]));
+            for dy in -distance..=distance {
+                for dx in -distance..=distance {
+                    let next = (x + dx, y + dy);
+                    if cells.contains_key(&next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        islands.push(island);
+    }
+    islands
+}
+
+/// Runs an island on its own until it comes back, giving the smallest of its
+/// phases' shapes in any orientation, which names the object whatever phase
+/// or orientation it was met in, and how it behaves.
+fn classify(cells: Vec<(i64, i64, CellState)>, rule: &impl Transition, reach: u32) -> (Shape, Kind) {
+    let mut universe = Universe::new();
+    for &(x, y, state) in &cells {
+        universe.set(x, y, state);
+    }
+    let (first, origin) = placed(cells);
+    let mut key = canonical(&first);
+    for generation in 1..=MAX_PERIOD {
+        universe.step(rule, reach);
+        let (shape, at) = placed(universe.iter().collect());
+        if shape.is_empty() {
+            break;
+        }
+        if shape == first {
+            let (dx, dy) = (at.0 - origin.0, at.1 - origin.1);
+            let kind = match (generation, dx, dy) {
+                (1, 0, 0) => Kind::Still,
+                (period, 0, 0) => Kind::Oscillator { period },
+                (period, dx, dy) => Kind::Spaceship { period, dx, dy },
+            };
+            return (key, kind);
+        }
+        key = key.min(canonical(&shape));
+    }
+    (canonical(&first), Kind::Unsettled)
+}
+
+/// `cells` moved so the smallest `x` and `y` are zero, and sorted, along with
+/// how far they were moved.
+fn placed(mut cells: Vec<(i64, i64, CellState)>) -> (Shape, (i64, i64)) {
+    let x0 = cells.iter().map(|&(x, _, _)| x).min().unwrap_or(0);
+    let y0 = cells.iter().map(|&(_, y, _)| y).min().unwrap_or(0);
+    for (x, y, _) in &mut cells {
+        *x -= x0;
+        *y -= y0;
+    }
+    cells.sort_unstable();
+    (cells, (x0, y0))
+}
+
+/// The smallest of a shape's eight rotations and reflections.
+fn canonical(shape: &Shape) -> Shape {
+    let orientations = [false, true].into_iter().flat_map(|swap| {
+        [(1, 1), (-1, 1), (1, -1), (-1, -1)].map(|(sx, sy)| (swap, sx, sy))
+    });
+    orientations
+        .map(|(swap, sx, sy)| {
+            let turned = shape.iter().map(|&(x, y, state)| {
+                let (x, y) = if swap { (y, x) } else { (x, y) };
+                (sx * x, sy * y, state)
+            });
+            placed(turned.collect()).0
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(universe: &mut Universe, rows: &[&str], x0: i64, y0: i64) {
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.bytes().enumerate() {
+                if c == b'o' {
+                    universe.set(x0 + x as i64, y0 - y as i64, ALIVE);
+                }
+            }
+        }
+    }
+
+    fn census(universe: &Universe) -> Census {
+        Census::take(universe, &Rule::life(), 1, &Library::life())
+    }
+
+    #[test]
+    fn names_known_objects() {
+        let mut universe = Universe::new();
+        place(&mut universe, &["oo", "oo"], 0, 0);
+        place(&mut universe, &["oo", "oo"], 40, 40);
+        place(&mut universe, &["ooo"], 20, 0);
+        place(&mut universe, &[".o.", "..o", "ooo"], 0, 20);
+        place(&mut universe, &[".o..o", "o....", "o...o", "oooo."], 40, 0);
+
+        let entries = census(&universe).entries;
+        let find = |name| entries.iter().find(|entry| entry.name == Some(name)).unwrap_or_else(|| panic!("no {name} in {entries:?}"));
+        assert_eq!(entries.len(), 4, "{entries:?}");
+        assert_eq!((find("block").kind, find("block").count), (Kind::Still, 2));
+        assert_eq!(find("blinker").kind, Kind::Oscillator { period: 2 });
+        assert!(matches!(find("glider").kind, Kind::Spaceship { period: 4, dx, dy } if dx.abs() == 1 && dy.abs() == 1));
+        let lwss = find("lightweight spaceship");
+        assert!(matches!(lwss.kind, Kind::Spaceship { period: 4, dx, dy: 0 } if dx.abs() == 2));
+        assert_eq!(lwss.kind.to_string(), "c/2 spaceship");
+    }
+
+    #[test]
+    fn matches_any_phase_and_orientation() {
+        let mut universe = Universe::new();
+        // a glider a step on, flipped, and the other blinker phase
+        place(&mut universe, &["o.o", ".oo", ".o."], 0, 0);
+        place(&mut universe, &["o", "o", "o"], 20, 0);
+        let entries = census(&universe).entries;
+        let names: HashSet<_> = entries.iter().filter_map(|entry| entry.name).collect();
+        assert_eq!(names, HashSet::from(["glider", "blinker"]));
+    }
+
+    #[test]
+    fn every_library_object_is_settled() {
+        let life = Rule::life();
+        for &(name, rows) in LIFE_OBJECTS {
+            let mut universe = Universe::new();
+            place(&mut universe, rows, 0, 0);
+            let census = Census::take(&universe, &life, 1, &Library::life());
+            assert_eq!(census.entries.len(), 1, "{name}: {:?}", census.entries);
+            assert_eq!(census.entries[0].name, Some(name));
+            assert_ne!(census.entries[0].kind, Kind::Unsettled, "{name}");
+        }
+    }
+
+    #[test]
+    fn exports_csv() {
+        let mut universe = Universe::new();
+        place(&mut universe, &["oo", "oo"], 0, 0);
+        place(&mut universe, &["oo", "oo"], 10, 0);
+        place(&mut universe, &["ooo"], 20, 0);
+        place(&mut universe, &["o.o", ".o."], 30, 0);
+        assert_eq!(
+            census(&universe).to_csv(),
+            "object,kind,period,cells,count\n\
+             block,still life,1,4,2\n\
+             3-cell object,unsettled,,3,1\n\
+             blinker,p2 oscillator,2,3,1\n"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! One-dimensional automata: Wolfram's elementary rules and totalistic
+//! k-colour rules.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::grid::{CellState, Grid, Transition};
+use crate::topology::Topology;
+
+/// Largest range a totalistic rule may look across.
+pub const MAX_RANGE: u8 = 10;
+
+/// A one-dimensional rule, run on the board as a spacetime diagram: row 0
+/// holds the current generation and each row above it is one generation
+/// further back, so every step draws a new row below the older ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementaryRule {
+    code: u128,
+    states: u8,
+    range: u8,
+    totalistic: bool,
+    /// Next state for each neighbourhood index or total.
+    table: Vec<CellState>,
+}
+
+impl ElementaryRule {
+    /// Wolfram's elementary rule `code`: two states, a cell and its two
+    /// neighbours.
+    pub fn wolfram(code: u8) -> Self {
+        Self {
+            code: code as u128,
+            states: 2,
+            range: 1,
+            totalistic: false,
+            table: (0..8).map(|i| code >> i & 1).collect(),
+        }
+    }
+
+    /// Totalistic rule `code` with `states` colours: the cell's next state is
+    /// the digit of `code`, written in base `states`, at the position given by
+    /// the sum of the `2 * range + 1` cells around it.
+    pub fn totalistic(code: u128, states: u8, range: u8) -> Result<Self, ElementaryParseError> {
+        if states < 2 {
+            return Err(ElementaryParseError::InvalidStates(states.to_string()));
+        }
+        if !(1..=MAX_RANGE).contains(&range) {
+            return Err(ElementaryParseError::InvalidRange(range.to_string()));
+        }
+        let sums = (2 * range as u32 + 1) * (states as u32 - 1) + 1;
+        let mut table = Vec::with_capacity(sums as usize);
+        let mut rest = code;
+        for _ in 0..sums {
+            table.push((rest % states as u128) as CellState);
+            rest /= states as u128;
+        }
+        if rest != 0 {
+            return Err(ElementaryParseError::CodeTooLarge(code));
+        }
+        Ok(Self {
+            code,
+            states,
+            range,
+            totalistic: true,
+            table,
+        })
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn range(&self) -> u8 {
+        self.range
+    }
+
+    /// The generation after `row`. Cells beyond either end are found through
+    /// `topology`, treating the row as a board one cell high.
+    pub fn next_row(&self, row: &[CellState], topology: Topology) -> Vec<CellState> {
+        let r = self.range as i32;
+        let width = row.len() as i32;
+        let get = |x: i32| topology.resolve(x, 0, width, 1).map_or(0, |(x, _)| row[x as usize]);
+        (0..row.len() as i32)
+            .map(|x| {
+                let key = if self.totalistic {
+                    (x - r..=x + r).map(|i| get(i) as usize).sum()
+                } else {
+                    (get(x - 1) << 2 | get(x) << 1 | get(x + 1)) as usize
+                };
+                self.table[key]
+            })
+            .collect()
+    }
+}
+
+impl Transition for ElementaryRule {
+    fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        let width = grid.width() as usize;
+        let cells: Vec<_> = grid.iter().map(|(_, _, state)| state).collect();
+        let mut next = self.next_row(&cells[..width], grid.topology());
+        next.extend_from_slice(&cells[..cells.len() - width]);
+        next
+    }
+}
+
+impl fmt::Display for ElementaryRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.totalistic {
+            return write!(f, "W{}", self.code);
+        }
+        write!(f, "T{}K{}", self.code, self.states)?;
+        if self.range != 1 {
+            write!(f, "R{}", self.range)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ElementaryRule {
+    type Err = ElementaryParseError;
+
+    /// Accepts Wolfram rules as `W110` and totalistic rules as `T777K3`,
+    /// with an optional range such as `T20K2R2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_uppercase();
+        let unknown = || ElementaryParseError::UnknownFormat(s.clone());
+        if let Some(code) = s.strip_prefix('W') {
+            return code
+                .parse::<u8>()
+                .map(Self::wolfram)
+                .map_err(|_| ElementaryParseError::InvalidCode(code.to_string()));
+        }
+
+        let rest = s.strip_prefix('T').ok_or_else(unknown)?;
+        let (code, rest) = rest.split_once('K').ok_or_else(unknown)?;
+        let (states, range) = rest.split_once('R').unwrap_or((rest, "1"));
+        let code = code
+            .parse()
+            .map_err(|_| ElementaryParseError::InvalidCode(code.to_string()))?;
+        let states = states
+            .parse()
+            .map_err(|_| ElementaryParseError::InvalidStates(states.to_string()))?;
+        let range = range
+            .parse()
+            .map_err(|_| ElementaryParseError::InvalidRange(range.to_string()))?;
+        Self::totalistic(code, states, range)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElementaryParseError {
+    UnknownFormat(String),
+    InvalidCode(String),
+    InvalidStates(String),
+    InvalidRange(String),
+    CodeTooLarge(u128),
+}
+
+impl fmt::Display for ElementaryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementaryParseError::UnknownFormat(s) => {
+                write!(f, "'{s}' is not a one-dimensional rule, expected something like W110 or T777K3")
+            }
+            ElementaryParseError::InvalidCode(s) => write!(f, "'{s}' is not a rule number"),
+            ElementaryParseError::InvalidStates(s) => {
+                write!(f, "'{s}' is not a colour count, expected a number from 2 to 255")
+            }
+            ElementaryParseError::InvalidRange(s) => {
+                write!(f, "'{s}' is not a range, expected 1 to {MAX_RANGE}")
+            }
+            ElementaryParseError::CodeTooLarge(code) => {
+                write!(f, "rule number {code} is too large for that many colours and range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ElementaryParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &str) -> Vec<CellState> {
+        cells.chars().map(|c| c.to_digit(10).unwrap_or(0) as CellState).collect()
+    }
+
+    fn run(rule: &ElementaryRule, start: &str, generations: usize, topology: Topology) -> Vec<Vec<CellState>> {
+        let mut rows = vec![row(start)];
+        for _ in 0..generations {
+            rows.push(rule.next_row(rows.last().unwrap(), topology));
+        }
+        rows
+    }
+
+    #[test]
+    fn rule_30_from_one_cell() {
+        let rows = run(&ElementaryRule::wolfram(30), "...1...", 3, Topology::Dead);
+        assert_eq!(rows, [row("...1..."), row("..111.."), row(".11..1."), row("11.1111")]);
+    }
+
+    #[test]
+    fn rule_90_draws_a_sierpinski_triangle() {
+        let rows = run(&ElementaryRule::wolfram(90), "....1....", 4, Topology::Dead);
+        assert_eq!(rows[1], row("...1.1..."));
+        assert_eq!(rows[2], row("..1...1.."));
+        assert_eq!(rows[3], row(".1.1.1.1."));
+        assert_eq!(rows[4], row("1.......1"));
+    }
+
+    #[test]
+    fn rows_wrap_round_a_torus() {
+        let rule = ElementaryRule::wolfram(90);
+        assert_eq!(rule.next_row(&row("1...."), Topology::Torus), row(".1..1"));
+        assert_eq!(rule.next_row(&row("1...."), Topology::Dead), row(".1..."));
+    }
+
+    #[test]
+    fn totalistic_rules_read_the_code_by_sum() {
+        let rule: ElementaryRule = "T777K3".parse().unwrap();
+        let rows = run(&rule, "...1...", 2, Topology::Dead);
+        assert_eq!(rows[1], row("..111.."));
+        assert_eq!(rows[2], row(".12121."));
+    }
+
+    #[test]
+    fn parses_and_prints_rules() {
+        for s in ["W110", "T777K3", "T20K2R2"] {
+            assert_eq!(s.parse::<ElementaryRule>().unwrap().to_string(), s);
+        }
+        assert_eq!("w30".parse::<ElementaryRule>(), Ok(ElementaryRule::wolfram(30)));
+        assert_eq!("W256".parse::<ElementaryRule>(), Err(ElementaryParseError::InvalidCode("256".into())));
+        assert_eq!("T64K2".parse::<ElementaryRule>(), Err(ElementaryParseError::CodeTooLarge(64)));
+        assert_eq!("T1K1".parse::<ElementaryRule>(), Err(ElementaryParseError::InvalidStates("1".into())));
+        assert_eq!("T1K2R11".parse::<ElementaryRule>(), Err(ElementaryParseError::InvalidRange("11".into())));
+        assert!(matches!("X110".parse::<ElementaryRule>(), Err(ElementaryParseError::UnknownFormat(_))));
+    }
+
+    #[test]
+    fn steps_push_older_generations_up() {
+        let mut grid = Grid::new(5, 3);
+        grid.set(2, 0, 1);
+        grid.step(&ElementaryRule::wolfram(90));
+        grid.step(&ElementaryRule::wolfram(90));
+        assert_eq!(grid.row(0), row("1...1"));
+        assert_eq!(grid.row(1), row(".1.1."));
+        assert_eq!(grid.row(2), row("..1.."));
+    }
+}
@@ -0,0 +1,199 @@
+//! Telling when a run has settled: died out, stopped changing, or started
+//! repeating itself. Each generation's board is reduced to a hash, and a
+//! board whose hash was seen a few generations earlier is taken to be the
+//! same board again.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Longest period looked for. Hashes older than this are forgotten.
+pub const MAX_PERIOD: u64 = 1024;
+
+/// How a run ended up, with the generation it got there.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fate {
+    /// Every cell is empty.
+    Extinct { generation: u64 },
+    /// The board is the same from one generation to the next.
+    Still { generation: u64 },
+    /// The board comes back to the same cells every `period` generations.
+    Oscillating { generation: u64, period: u64 },
+}
+
+/// Watches the hashes of consecutive generations for a repeat.
+#[derive(Debug, Clone, Default)]
+pub struct CycleDetector {
+    seen: HashMap<u64, u64>,
+    order: VecDeque<(u64, u64)>,
+    last: Option<u64>,
+    /// Generations between the boards looked at, once there are two.
+    stride: Option<u64>,
+    fate: Option<Fate>,
+}
+
+impl CycleDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes in the board at `generation`, given as its hash and whether it is
+    /// empty. Returns the run's fate on the generation it is first found.
+    /// Looking at the same generation twice is harmless. Boards may be taken
+    /// in every few generations, as HashLife jumps, but a gap unlike the last
+    /// starts the watch over, as the board may have been edited in between.
+    /// Past a gap of one, a period found is a multiple of the true one; see
+    /// [`CycleDetector::narrow`].
+    pub fn observe(&mut self, generation: u64, hash: u64, empty: bool) -> Option<Fate> {
+        match self.last.map(|last| generation.checked_sub(last)) {
+            Some(Some(0)) => return None,
+            Some(Some(gap)) if self.stride.is_none_or(|stride| stride == gap) => self.stride = Some(gap),
+            Some(_) => self.forget(),
+            None => {}
+        }
+        self.last = Some(generation);
+        if self.fate.is_some() {
+            return None;
+        }
+
+        let fate = if empty {
+            Some(Fate::Extinct { generation })
+        } else {
+            self.seen.get(&hash).map(|&first| match generation - first {
+                1 => Fate::Still { generation: first },
+                period => Fate::Oscillating { generation: first, period },
+            })
+        };
+        if fate.is_some() {
+            self.fate = fate;
+            return fate;
+        }
+
+        self.seen.insert(hash, generation);
+        self.order.push_back((generation, hash));
+        while self.order.len() as u64 > MAX_PERIOD {
+            if let Some((old, hash)) = self.order.pop_front() {
+                if self.seen.get(&hash) == Some(&old) {
+                    self.seen.remove(&hash);
+                }
+            }
+        }
+        None
+    }
+
+    /// The fate found since the watch last started over, if any.
+    pub fn fate(&self) -> Option<Fate> {
+        self.fate
+    }
+
+    /// Settles the period of an oscillation found every few generations on
+    /// `period`, its true one, which is a still life if it is 1. Returns the
+    /// fate as it now stands.
+    pub fn narrow(&mut self, period: u64) -> Option<Fate> {
+        if let Some(Fate::Still { generation } | Fate::Oscillating { generation, .. }) = self.fate {
+            self.fate = Some(match period {
+                1 => Fate::Still { generation },
+                period => Fate::Oscillating { generation, period },
+            });
+        }
+        self.fate
+    }
+
+    /// Starts the watch over, for a board that was changed by hand.
+    pub fn forget(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.last = None;
+        self.stride = None;
+        self.fate = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::ALIVE;
+    use crate::rule::Rule;
+    use crate::universe::Universe;
+
+    /// Steps `cells` under Life until the detector finds its fate, if it does
+    /// within `generations`.
+    fn fate_of(cells: &[(i64, i64)], generations: u64) -> Option<Fate> {
+        let mut universe = Universe::new();
+        for &(x, y) in cells {
+            universe.set(x, y, ALIVE);
+        }
+        let mut detector = CycleDetector::new();
+        for generation in 0..generations {
+            if let Some(fate) = detector.observe(generation, universe.zobrist(), universe.is_empty()) {
+                return Some(fate);
+            }
+            universe.step(&Rule::life(), 1);
+        }
+        None
+    }
+
+    #[test]
+    fn blinker_oscillates_with_period_two() {
+        assert_eq!(fate_of(&[(0, 0), (1, 0), (2, 0)], 10), Some(Fate::Oscillating { generation: 0, period: 2 }));
+    }
+
+    #[test]
+    fn block_is_still_and_a_lone_cell_dies() {
+        assert_eq!(fate_of(&[(0, 0), (1, 0), (0, 1), (1, 1)], 10), Some(Fate::Still { generation: 0 }));
+        assert_eq!(fate_of(&[(5, 5)], 10), Some(Fate::Extinct { generation: 1 }));
+        // a beehive settles from a row of four
+        assert_eq!(fate_of(&[(0, 0), (1, 0), (2, 0), (3, 0)], 10), Some(Fate::Still { generation: 2 }));
+    }
+
+    #[test]
+    fn glider_never_repeats_its_cells() {
+        assert_eq!(fate_of(&[(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)], 200), None);
+    }
+
+    #[test]
+    fn skipping_a_generation_starts_over() {
+        let mut detector = CycleDetector::new();
+        assert_eq!(detector.observe(0, 7, false), None);
+        assert_eq!(detector.observe(0, 7, false), None);
+        assert_eq!(detector.observe(1, 8, false), None);
+        assert_eq!(detector.observe(3, 7, false), None);
+        assert_eq!(detector.observe(4, 7, false), Some(Fate::Still { generation: 3 }));
+        assert_eq!(detector.observe(5, 7, false), None);
+        assert_eq!(detector.fate(), Some(Fate::Still { generation: 3 }));
+
+        // going back is a skip too
+        detector.forget();
+        assert_eq!(detector.observe(2, 7, false), None);
+        assert_eq!(detector.observe(3, 7, false), Some(Fate::Still { generation: 2 }));
+        assert_eq!(detector.observe(4, 7, false), None);
+        assert_eq!(detector.fate(), Some(Fate::Still { generation: 2 }));
+
+        detector.forget();
+        assert_eq!(detector.fate(), None);
+        assert_eq!(detector.observe(4, 7, false), None);
+    }
+
+    #[test]
+    fn periods_past_the_limit_go_unseen() {
+        let mut detector = CycleDetector::new();
+        let period = MAX_PERIOD + 1;
+        for generation in 0..3 * period {
+            assert_eq!(detector.observe(generation, generation % period, false), None);
+        }
+    }
+
+    #[test]
+    fn watches_every_few_generations() {
+        // a blinker seen every four generations looks the same each time
+        let mut detector = CycleDetector::new();
+        assert_eq!(detector.observe(8, 1, false), None);
+        assert_eq!(detector.observe(12, 1, false), Some(Fate::Oscillating { generation: 8, period: 4 }));
+        assert_eq!(detector.narrow(2), Some(Fate::Oscillating { generation: 8, period: 2 }));
+        assert_eq!(detector.narrow(1), Some(Fate::Still { generation: 8 }));
+
+        let mut detector = CycleDetector::new();
+        detector.observe(0, 1, false);
+        detector.observe(4, 2, false);
+        assert_eq!(detector.observe(6, 1, false), None);
+        assert_eq!(detector.narrow(1), None);
+    }
+}
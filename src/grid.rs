@@ -0,0 +1,264 @@
+use crate::topology::Topology;
+
+/// Cell states are plain numbers: 0 is empty, 1 is alive and anything above
+/// that is one of the rule's dying (refractory) states.
+pub type CellState = u8;
+
+pub const EMPTY: CellState = 0;
+pub const ALIVE: CellState = 1;
+
+/// A rule that can work out the next generation of a whole board.
+pub trait Transition {
+    /// The state of every cell on `grid` one generation on, row by row.
+    fn next_states(&self, grid: &Grid) -> Vec<CellState>;
+}
+
+/// A bounded board of `width` x `height` cells. What lies outside the board
+/// is decided by its [`Topology`]; by default everything there is empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: i32,
+    height: i32,
+    cells: Vec<CellState>,
+    topology: Topology,
+}
+
+impl Grid {
+    pub fn new(width: i32, height: i32) -> Self {
+        assert!(width > 0 && height > 0, "grid dimensions must be positive");
+        Self {
+            width,
+            height,
+            cells: vec![EMPTY; (width * height) as usize],
+            topology: Topology::default(),
+        }
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    /// The cell at `(x, y)`. Coordinates off the board are carried back onto
+    /// it by the board's topology.
+    pub fn get(&self, x: i32, y: i32) -> CellState {
+        match self.topology.resolve(x, y, self.width, self.height) {
+            Some((x, y)) => self.cells[(x + y * self.width) as usize],
+            None => EMPTY,
+        }
+    }
+
+    /// Sets the cell at `(x, y)`. Returns `false` if it lies outside the board.
+    pub fn set(&mut self, x: i32, y: i32, state: CellState) -> bool {
+        match self.index(x, y) {
+            Some(i) => {
+                self.cells[i] = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_alive(&self, x: i32, y: i32) -> bool {
+        self.get(x, y) == ALIVE
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&c| c == ALIVE).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(EMPTY);
+    }
+
+    /// Empties every cell in a state that a rule with `states` states lacks.
+    pub fn clamp_states(&mut self, states: u8) {
+        for cell in self.cells.iter_mut().filter(|c| **c >= states) {
+            *cell = EMPTY;
+        }
+    }
+
+    /// Rows `y0..y0 + rows` with a margin of `pad` cells all round, filled in
+    /// through the board's topology, as a grid with dead edges. Stepping the
+    /// band with a rule that looks no more than `pad` cells away gives the
+    /// same middle rows as stepping the whole board.
+    pub fn band(&self, y0: i32, rows: i32, pad: i32) -> Grid {
+        let mut band = Grid::new(self.width + 2 * pad, rows + 2 * pad);
+        for y in 0..band.height {
+            for x in 0..band.width {
+                band.cells[(x + y * band.width) as usize] = self.get(x - pad, y0 + y - pad);
+            }
+        }
+        band
+    }
+
+    /// Overwrites the rows from `y0` up with `cells`, given row by row.
+    pub fn set_rows(&mut self, y0: i32, cells: &[CellState]) {
+        let start = (y0 * self.width) as usize;
+        self.cells[start..start + cells.len()].copy_from_slice(cells);
+    }
+
+    /// The cells of row `y`, left to right.
+    pub fn row(&self, y: i32) -> &[CellState] {
+        let start = (y * self.width) as usize;
+        &self.cells[start..start + self.width as usize]
+    }
+
+    /// Iterates over every cell as `(x, y, state)`, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, &state)| (i as i32 % self.width, i as i32 / self.width, state))
+    }
+
+    pub fn live_neighbours(&self, x: i32, y: i32) -> u8 {
+        let mut count = 0;
+        for yi in (y - 1)..(y + 2) {
+            for xi in (x - 1)..(x + 2) {
+                if (xi != x || yi != y) && self.is_alive(xi, yi) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Advances the board by one generation of `rule`.
+    pub fn step(&mut self, rule: &impl Transition) {
+        self.cells = rule.next_states(self);
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if self.contains(x, y) {
+            Some((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// A board drawn top row first, with `o` alive and anything else empty.
+#[cfg(test)]
+pub(crate) fn board(rows: &[&str]) -> Grid {
+    keyed_board(rows, "o")
+}
+
+/// A board drawn top row first, with characters standing for states in the
+/// order of `key` and anything else empty.
+#[cfg(test)]
+pub(crate) fn keyed_board(rows: &[&str], key: &str) -> Grid {
+    let height = rows.len() as i32;
+    let mut grid = Grid::new(rows[0].len() as i32, height);
+    for (r, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let state = key.find(c).map_or(EMPTY, |i| i as CellState + 1);
+            grid.set(x as i32, height - 1 - r as i32, state);
+        }
+    }
+    grid
+}
+
+/// A random board with about two cells in five filled, each in a state
+/// below `states` other than empty, the same for the same `seed`.
+#[cfg(test)]
+pub(crate) fn soup(width: i32, height: i32, seed: u64, states: u8) -> Grid {
+    let mut rng = crate::noise::Rng::new(seed);
+    let mut grid = Grid::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            if rng.chance(0.4) {
+                grid.set(x, y, 1 + (rng.next_u64() % (states as u64 - 1)) as CellState);
+            }
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    #[test]
+    fn cells_off_the_board_are_empty() {
+        let mut grid = Grid::new(3, 2);
+        assert!(grid.set(2, 1, ALIVE));
+        assert!(!grid.set(3, 1, ALIVE));
+        assert!(!grid.set(0, -1, ALIVE));
+        assert_eq!(grid.get(2, 1), ALIVE);
+        assert_eq!(grid.get(-1, 1), EMPTY);
+        assert_eq!(grid.population(), 1);
+    }
+
+    #[test]
+    fn counts_live_neighbours() {
+        let grid = board(&["oo.", ".oo", "o.."]);
+        assert_eq!(grid.live_neighbours(1, 1), 4);
+        assert_eq!(grid.live_neighbours(0, 0), 1);
+        assert_eq!(grid.live_neighbours(2, 2), 3);
+    }
+
+    #[test]
+    fn block_stays_and_blinker_flips() {
+        let block = board(&["....", ".oo.", ".oo.", "...."]);
+        let mut grid = block.clone();
+        grid.step(&Rule::life());
+        assert_eq!(grid, block);
+
+        let mut blinker = board(&[".....", ".....", ".ooo.", ".....", "....."]);
+        blinker.step(&Rule::life());
+        assert_eq!(blinker, board(&[".....", "..o..", "..o..", "..o..", "....."]));
+        blinker.step(&Rule::life());
+        assert_eq!(blinker, board(&[".....", ".....", ".ooo.", ".....", "....."]));
+    }
+
+    #[test]
+    fn glider_moves_one_cell_diagonally_every_four_generations() {
+        let mut grid = board(&[".o....", "..o...", "ooo...", "......", "......", "......"]);
+        for _ in 0..8 {
+            grid.step(&Rule::life());
+        }
+        assert_eq!(grid, board(&["......", "......", "...o..", "....o.", "..ooo.", "......"]));
+    }
+
+    #[test]
+    fn stepping_bands_matches_stepping_the_board() {
+        let mut whole = board(&["........", ".o...oo.", "..o..o..", "ooo.....", "....ooo.", ".....o..", "o.......", "oo....oo"]);
+        let mut banded = whole.clone();
+        for _ in 0..5 {
+            let mut next = vec![];
+            for y0 in (0..8).step_by(3) {
+                let rows = 3.min(8 - y0);
+                let band = banded.band(y0, rows, 1);
+                let cells = Rule::life().next_states(&band);
+                for y in 1..=rows {
+                    next.extend_from_slice(&cells[(y * 10 + 1) as usize..(y * 10 + 9) as usize]);
+                }
+            }
+            banded.set_rows(0, &next);
+            whole.step(&Rule::life());
+            assert_eq!(banded, whole);
+        }
+    }
+}
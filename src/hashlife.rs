@@ -0,0 +1,468 @@
+//! Gosper's HashLife: the board as a quadtree of shared, hash-consed nodes,
+//! where the future of every node is memoised, so regular patterns can be
+//! jumped exponentially far ahead.
+
+use std::collections::HashMap;
+use crate::grid::{CellState, Grid, Transition, ALIVE, EMPTY};
+use crate::universe::Universe;
+
+/// Largest step a single [`HashLife::step`] may take, as a power of two.
+pub const MAX_STEP: u8 = 40;
+
+/// The tree is rebuilt from the live pattern once it holds this many nodes.
+const NODE_LIMIT: usize = 1 << 22;
+
+/// Deepest tree allowed, so every cell still has an `i64` coordinate.
+const MAX_LEVEL: u8 = 62;
+
+type NodeId = u32;
+
+/// A node is either one cell, or four children of the level below, ordered
+/// bottom-left, bottom-right, top-left, top-right.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Leaf(CellState),
+    Branch([NodeId; 4]),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    key: Key,
+    /// A level `n` node is `2^n` cells across.
+    level: u8,
+    /// Non-empty cells under the node.
+    filled: u64,
+    /// Live cells under the node.
+    population: u64,
+}
+
+/// A HashLife universe stepping `rule`, which must only look one cell away:
+/// Moore, von Neumann or hexagonal neighbourhoods of range 1, with any number
+/// of states. The root is centred on the origin and grows as the pattern does.
+#[derive(Debug, Clone)]
+pub struct HashLife<R> {
+    rule: R,
+    nodes: Vec<Node>,
+    index: HashMap<Key, NodeId>,
+    /// `(node, j)` to the centre of `node` advanced `2^j` generations.
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// The empty node of every level, once made.
+    empties: Vec<NodeId>,
+    root: NodeId,
+}
+
+impl<R: Transition> HashLife<R> {
+    pub fn new(rule: R) -> Self {
+        let mut life = Self {
+            rule,
+            nodes: vec![],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empties: vec![],
+            root: 0,
+        };
+        life.root = life.empty(3);
+        life
+    }
+
+    /// A HashLife copy of every cell in `universe`.
+    pub fn from_universe(rule: R, universe: &Universe) -> Self {
+        let mut life = Self::new(rule);
+        for (x, y, state) in universe.iter() {
+            life.set(x, y, state);
+        }
+        life
+    }
+
+    /// Every non-empty cell, back in a [`Universe`].
+    pub fn to_universe(&self) -> Universe {
+        let mut universe = Universe::new();
+        let half = self.half();
+        self.visit(self.root, -half, -half, &mut |x, y, state| universe.set(x, y, state));
+        universe
+    }
+
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> CellState {
+        let half = self.half();
+        if x < -half || x >= half || y < -half || y >= half {
+            return EMPTY;
+        }
+        let (mut id, mut x, mut y) = (self.root, x + half, y + half);
+        loop {
+            let node = &self.nodes[id as usize];
+            match node.key {
+                Key::Leaf(state) => return state,
+                _ if node.filled == 0 => return EMPTY,
+                Key::Branch(children) => {
+                    let half = 1i64 << (node.level - 1);
+                    let q = (x >= half) as usize | ((y >= half) as usize) << 1;
+                    id = children[q];
+                    x %= half;
+                    y %= half;
+                }
+            }
+        }
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, state: CellState) {
+        while !self.contains(x, y) && self.level(self.root) < MAX_LEVEL {
+            self.root = self.expand(self.root);
+        }
+        if !self.contains(x, y) {
+            return;
+        }
+        let half = self.half();
+        self.root = self.set_in(self.root, x + half, y + half, state);
+    }
+
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes[self.root as usize].filled == 0
+    }
+
+    /// Nodes currently held, shared subtrees counted once.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Counts the non-empty cells of the rectangle from `(x0, y0)` to
+    /// `(x1, y1)` inclusive in aligned blocks `2^level` cells across, as
+    /// [`Universe::filled_blocks`] does. Whole nodes are counted at once, so
+    /// the cost follows the number of blocks rather than the cells in them.
+    pub fn filled_blocks(&self, level: u8, rect: (i64, i64, i64, i64), mut count: impl FnMut(i64, i64, u64)) {
+        let half = self.half();
+        self.count_blocks(self.root, -half, -half, level, rect, &mut count);
+    }
+
+    /// Advances the universe by `2^k` generations at once. `k` is capped at
+    /// [`MAX_STEP`].
+    pub fn step(&mut self, k: u8) {
+        let k = k.min(MAX_STEP);
+        // room for the pattern to travel 2^k cells either way before it can
+        // leave the centre that the result keeps
+        while self.level(self.root) < k + 2 || !self.is_centred(self.root) {
+            self.root = self.expand(self.root);
+        }
+        let root = self.expand(self.root);
+        self.root = self.result(root, k);
+        if self.nodes.len() > NODE_LIMIT {
+            self.collect();
+        }
+    }
+
+    fn half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        x >= -half && x < half && y >= -half && y < half
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.nodes[id as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        match self.nodes[id as usize].key {
+            Key::Branch(children) => children,
+            Key::Leaf(_) => unreachable!("leaves have no children"),
+        }
+    }
+
+    fn intern(&mut self, key: Key) -> NodeId {
+        if let Some(&id) = self.index.get(&key) {
+            return id;
+        }
+        let node = match key {
+            Key::Leaf(state) => Node {
+                key,
+                level: 0,
+                filled: (state != EMPTY) as u64,
+                population: (state == ALIVE) as u64,
+            },
+            Key::Branch(children) => {
+                let nodes = children.map(|c| &self.nodes[c as usize]);
+                Node {
+                    key,
+                    level: nodes[0].level + 1,
+                    filled: nodes.iter().fold(0u64, |sum, n| sum.saturating_add(n.filled)),
+                    population: nodes.iter().fold(0u64, |sum, n| sum.saturating_add(n.population)),
+                }
+            }
+        };
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.index.insert(key, id);
+        id
+    }
+
+    fn leaf(&mut self, state: CellState) -> NodeId {
+        self.intern(Key::Leaf(state))
+    }
+
+    fn branch(&mut self, children: [NodeId; 4]) -> NodeId {
+        self.intern(Key::Branch(children))
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empties.len() <= level as usize {
+            let id = match self.empties.last() {
+                None => self.leaf(EMPTY),
+                Some(&below) => self.branch([below; 4]),
+            };
+            self.empties.push(id);
+        }
+        self.empties[level as usize]
+    }
+
+    /// The node one level up with `id` in its middle.
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let children = self.children(id);
+        let empty = self.empty(self.level(id) - 1);
+        let quadrants: [NodeId; 4] = std::array::from_fn(|q| {
+            let mut around = [empty; 4];
+            around[3 - q] = children[q];
+            around
+        })
+        .map(|around| self.branch(around));
+        self.branch(quadrants)
+    }
+
+    /// The middle half of a node, one level down.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [sw, se, nw, ne] = self.children(id).map(|c| self.children(c));
+        self.branch([sw[3], se[2], nw[1], ne[0]])
+    }
+
+    /// Whether every cell of a node lies in its middle quarter.
+    fn is_centred(&mut self, id: NodeId) -> bool {
+        if self.level(id) < 3 {
+            return false;
+        }
+        let centre = self.centre(id);
+        let inner = self.centre(centre);
+        self.nodes[inner as usize].filled == self.nodes[id as usize].filled
+    }
+
+    fn set_in(&mut self, id: NodeId, x: i64, y: i64, state: CellState) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return self.leaf(state);
+        }
+        let half = 1i64 << (level - 1);
+        let q = (x >= half) as usize | ((y >= half) as usize) << 1;
+        let mut children = self.children(id);
+        children[q] = self.set_in(children[q], x % half, y % half, state);
+        self.branch(children)
+    }
+
+    fn visit(&self, id: NodeId, x: i64, y: i64, emit: &mut impl FnMut(i64, i64, CellState)) {
+        let node = &self.nodes[id as usize];
+        if node.filled == 0 {
+            return;
+        }
+        match node.key {
+            Key::Leaf(state) => emit(x, y, state),
+            Key::Branch(children) => {
+                let half = 1i64 << (node.level - 1);
+                for (q, child) in children.into_iter().enumerate() {
+                    self.visit(child, x + (q & 1) as i64 * half, y + (q >> 1) as i64 * half, emit);
+                }
+            }
+        }
+    }
+
+    fn count_blocks(
+        &self,
+        id: NodeId,
+        x: i64,
+        y: i64,
+        level: u8,
+        rect: (i64, i64, i64, i64),
+        count: &mut impl FnMut(i64, i64, u64),
+    ) {
+        let node = &self.nodes[id as usize];
+        let (x0, y0, x1, y1) = rect;
+        let size = 1i64 << node.level;
+        let outside = x + size <= x0 || x > x1 || y + size <= y0 || y > y1;
+        if node.filled == 0 || outside {
+            return;
+        }
+        let inside = x >= x0 && x + size - 1 <= x1 && y >= y0 && y + size - 1 <= y1;
+        if node.level <= level && inside {
+            count(x >> level, y >> level, node.filled);
+            return;
+        }
+        if let Key::Branch(children) = node.key {
+            let half = size / 2;
+            for (q, child) in children.into_iter().enumerate() {
+                self.count_blocks(child, x + (q & 1) as i64 * half, y + (q >> 1) as i64 * half, level, rect, count);
+            }
+        }
+    }
+
+    /// The middle half of a level `n` node, advanced `2^j` generations, where
+    /// `j <= n - 2`.
+    fn result(&mut self, id: NodeId, j: u8) -> NodeId {
+        let level = self.level(id);
+        if self.nodes[id as usize].filled == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&cached) = self.results.get(&(id, j)) {
+            return cached;
+        }
+
+        let result = if level == 2 {
+            self.base_result(id)
+        } else {
+            // the sixteen grandchildren, bottom row first
+            let quadrants = self.children(id).map(|c| self.children(c));
+            let g = |x: usize, y: usize| quadrants[(x >> 1) | (y >> 1) << 1][(x & 1) | (y & 1) << 1];
+            let mut overlaps = [[0; 3]; 3];
+            for (y, row) in overlaps.iter_mut().enumerate() {
+                for (x, node) in row.iter_mut().enumerate() {
+                    *node = self.branch([g(x, y), g(x + 1, y), g(x, y + 1), g(x + 1, y + 1)]);
+                }
+            }
+
+            // full speed takes two half steps, slower steps skip the first
+            let full = j == level - 2;
+            for node in overlaps.iter_mut().flatten() {
+                *node = if full { self.result(*node, j - 1) } else { self.centre(*node) };
+            }
+            let o = overlaps;
+            let quarters = [
+                [o[0][0], o[0][1], o[1][0], o[1][1]],
+                [o[0][1], o[0][2], o[1][1], o[1][2]],
+                [o[1][0], o[1][1], o[2][0], o[2][1]],
+                [o[1][1], o[1][2], o[2][1], o[2][2]],
+            ];
+            let step = if full { j - 1 } else { j };
+            let mut children = [0; 4];
+            for (child, quarter) in children.iter_mut().zip(quarters) {
+                let node = self.branch(quarter);
+                *child = self.result(node, step);
+            }
+            self.branch(children)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// One generation of a 4x4 node, worked out by the rule itself.
+    fn base_result(&mut self, id: NodeId) -> NodeId {
+        let mut grid = Grid::new(4, 4);
+        self.visit(id, 0, 0, &mut |x, y, state| {
+            grid.set(x as i32, y as i32, state);
+        });
+        let next = self.rule.next_states(&grid);
+        let cell = |x: usize, y: usize| next[x + y * 4];
+        let centre = [cell(1, 1), cell(2, 1), cell(1, 2), cell(2, 2)];
+        let leaves = centre.map(|state| self.leaf(state));
+        self.branch(leaves)
+    }
+
+    /// Rebuilds the tree with only the nodes the pattern still uses.
+    fn collect(&mut self) {
+        let old = std::mem::take(&mut self.nodes);
+        self.index.clear();
+        self.results.clear();
+        self.empties.clear();
+        let mut moved = HashMap::new();
+        self.root = self.copy_node(&old, self.root, &mut moved);
+    }
+
+    fn copy_node(&mut self, old: &[Node], id: NodeId, moved: &mut HashMap<NodeId, NodeId>) -> NodeId {
+        if let Some(&new) = moved.get(&id) {
+            return new;
+        }
+        let new = match old[id as usize].key {
+            Key::Leaf(state) => self.leaf(state),
+            Key::Branch(children) => {
+                let children = children.map(|child| self.copy_node(old, child, moved));
+                self.branch(children)
+            }
+        };
+        moved.insert(id, new);
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid;
+    use crate::rule::Rule;
+
+    /// A soup in the middle of the plane.
+    fn soup(seed: u64, states: u8) -> Universe {
+        let mut universe = Universe::new();
+        universe.paste(&grid::soup(24, 24, seed, states), -12, -12);
+        universe
+    }
+
+    #[test]
+    fn sets_and_gets_cells_anywhere() {
+        let mut life = HashLife::new(Rule::life());
+        for (x, y) in [(0, 0), (-1, -1), (5_000, -7), (-1 << 40, 1 << 40)] {
+            life.set(x, y, ALIVE);
+            assert_eq!(life.get(x, y), ALIVE);
+        }
+        assert_eq!(life.population(), 4);
+        assert_eq!(life.get(1, 0), EMPTY);
+        life.set(0, 0, EMPTY);
+        assert_eq!(life.population(), 3);
+        let universe = life.to_universe();
+        assert_eq!(HashLife::from_universe(Rule::life(), &universe).to_universe(), universe);
+    }
+
+    #[test]
+    fn agrees_with_stepping_the_universe() {
+        for rule in ["B3/S23", "B36/S23", "B2/S34H", "B2/S/C3", "B3/S23V", "B2-a/S12"] {
+            let rule: Rule = rule.parse().unwrap();
+            let mut universe = soup(11, rule.states());
+            let mut life = HashLife::from_universe(rule.clone(), &universe);
+            let mut generation = 0;
+            for k in [0, 0, 1, 3, 2, 5] {
+                life.step(k);
+                for _ in 0..1 << k {
+                    universe.step(&rule, 1);
+                }
+                generation += 1 << k;
+                assert_eq!(life.to_universe(), universe, "{rule} at generation {generation}");
+            }
+        }
+    }
+
+    #[test]
+    fn jumps_a_glider_far_ahead() {
+        let mut life = HashLife::new(Rule::life());
+        for (x, y) in [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)] {
+            life.set(x, y, ALIVE);
+        }
+        life.step(20);
+        let shift = 1 << 18;
+        let cells: Vec<_> = [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)].iter().map(|&(x, y)| (x + shift, y - shift)).collect();
+        assert_eq!(life.population(), 5);
+        assert!(cells.iter().all(|&(x, y)| life.get(x, y) == ALIVE));
+    }
+
+    #[test]
+    fn counts_blocks_like_the_universe() {
+        let universe = soup(12, 2);
+        let life = HashLife::from_universe(Rule::life(), &universe);
+        let rect = (-10, -7, 9, 11);
+        let (mut from_life, mut from_universe) = (HashMap::new(), HashMap::new());
+        life.filled_blocks(2, rect, |x, y, n| *from_life.entry((x, y)).or_insert(0) += n);
+        universe.filled_blocks(2, rect, |x, y, n| *from_universe.entry((x, y)).or_insert(0) += n);
+        assert_eq!(from_life, from_universe);
+    }
+}
@@ -0,0 +1,157 @@
+//! Hensel notation for isotropic non-totalistic rules. Each neighbour count
+//! is split into letter classes by the shape the live neighbours make, up to
+//! rotation and reflection.
+
+use crate::grid::{Grid, ALIVE};
+
+/// The Moore neighbours in configuration bit order: bit 0 is north, then
+/// clockwise round to north-west, with north at `y + 1`.
+pub const RING: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+/// Letters valid for each neighbour count, in canonical order.
+pub const LETTERS: [&str; 9] = ["", "ce", "cekain", "cekainyqjr", "cekainyqjrtwz", "cekainyqjr", "cekain", "ce", ""];
+
+const N: u8 = 1;
+const NE: u8 = 1 << 1;
+const E: u8 = 1 << 2;
+const SE: u8 = 1 << 3;
+const S: u8 = 1 << 4;
+const SW: u8 = 1 << 5;
+const W: u8 = 1 << 6;
+const NW: u8 = 1 << 7;
+
+/// One representative configuration per letter for counts up to four.
+/// Counts five to seven are the complements of three down to one.
+const REPRESENTATIVES: [(u8, char, u8); 31] = [
+    (1, 'c', NE),
+    (1, 'e', N),
+    (2, 'c', NE | SE),
+    (2, 'e', N | E),
+    (2, 'k', N | SE),
+    (2, 'a', N | NE),
+    (2, 'i', N | S),
+    (2, 'n', NE | SW),
+    (3, 'c', NE | SE | SW),
+    (3, 'e', N | E | S),
+    (3, 'k', N | E | SW),
+    (3, 'a', N | NE | E),
+    (3, 'i', NW | N | NE),
+    (3, 'n', N | NE | SE),
+    (3, 'y', N | SE | SW),
+    (3, 'q', N | NE | SW),
+    (3, 'j', N | NE | W),
+    (3, 'r', N | NE | S),
+    (4, 'c', NE | SE | SW | NW),
+    (4, 'e', N | E | S | W),
+    (4, 'k', N | NE | SE | W),
+    (4, 'a', N | NE | E | SE),
+    (4, 'i', N | NE | SE | S),
+    (4, 'n', N | NE | SE | NW),
+    (4, 'y', N | NE | SE | SW),
+    (4, 'q', N | NE | E | SW),
+    (4, 'j', N | NE | S | W),
+    (4, 'r', N | NE | E | S),
+    (4, 't', N | SE | S | SW),
+    (4, 'w', N | NE | SW | W),
+    (4, 'z', N | NE | S | SW),
+];
+
+/// The letter class of every neighbourhood configuration, indexed by its
+/// configuration bits. Counts 0 and 8 have no letters and map to `' '`.
+pub fn letter_classes() -> [char; 256] {
+    let mut classes = [' '; 256];
+    for &(count, letter, config) in &REPRESENTATIVES {
+        for image in symmetries(config) {
+            classes[image as usize] = letter;
+            if count < 4 {
+                classes[!image as usize] = letter;
+            }
+        }
+    }
+    classes
+}
+
+type Transform = fn(i32, i32) -> (i32, i32);
+
+/// All images of `config` under the eight rotations and reflections.
+fn symmetries(config: u8) -> Vec<u8> {
+    let transforms: [Transform; 8] = [
+        |x, y| (x, y),
+        |x, y| (-y, x),
+        |x, y| (-x, -y),
+        |x, y| (y, -x),
+        |x, y| (-x, y),
+        |x, y| (x, -y),
+        |x, y| (y, x),
+        |x, y| (-y, -x),
+    ];
+    transforms
+        .iter()
+        .map(|transform| {
+            (0..8)
+                .filter(|&bit| config & (1 << bit) != 0)
+                .map(|bit| {
+                    let (x, y) = RING[bit];
+                    let image = transform(x, y);
+                    1 << RING.iter().position(|&o| o == image).unwrap()
+                })
+                .fold(0, |acc, bit: u8| acc | bit)
+        })
+        .collect()
+}
+
+/// Configuration bits of every cell on `grid`, row by row.
+pub fn configurations(grid: &Grid) -> Vec<u16> {
+    grid.iter()
+        .map(|(x, y, _)| {
+            RING.iter()
+                .enumerate()
+                .filter(|(_, &(dx, dy))| grid.get(x + dx, y + dy) == ALIVE)
+                .fold(0, |acc, (bit, _)| acc | 1 << bit)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_configuration_has_a_letter_of_its_count() {
+        let classes = letter_classes();
+        for (config, &class) in classes.iter().enumerate() {
+            let count = config.count_ones() as usize;
+            match count {
+                0 | 8 => assert_eq!(class, ' '),
+                _ => assert!(LETTERS[count].contains(class), "{config:08b}"),
+            }
+        }
+    }
+
+    #[test]
+    fn classes_have_the_known_sizes() {
+        let classes = letter_classes();
+        let size = |count: u32, letter: char| (0..256usize).filter(|&c| c.count_ones() == count && classes[c] == letter).count();
+        assert_eq!([size(1, 'c'), size(1, 'e')], [4, 4]);
+        assert_eq!(LETTERS[2].chars().map(|l| size(2, l)).collect::<Vec<_>>(), [4, 4, 8, 8, 2, 2]);
+        assert_eq!([size(4, 'e'), size(4, 'c'), size(4, 'z'), size(4, 't')], [1, 1, 4, 4]);
+    }
+
+    #[test]
+    fn classes_are_the_same_under_rotation_and_reflection() {
+        let classes = letter_classes();
+        for config in 0..=255u8 {
+            for image in symmetries(config) {
+                assert_eq!(classes[image as usize], classes[config as usize], "{config:08b} and {image:08b}");
+            }
+        }
+    }
+
+    #[test]
+    fn configurations_start_at_north() {
+        let mut grid = Grid::new(3, 3);
+        grid.set(1, 2, ALIVE);
+        grid.set(2, 1, ALIVE);
+        assert_eq!(configurations(&grid)[4], (N | E) as u16);
+    }
+}
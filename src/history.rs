@@ -0,0 +1,182 @@
+//! Undo and redo. An edit is kept as the cells it changed, each with its value
+//! before and after, so undoing it puts the old values back and redoing it
+//! puts the new ones back again.
+
+use std::collections::VecDeque;
+
+/// Edits kept for undoing; the oldest are forgotten beyond this.
+pub const MAX_EDITS: usize = 256;
+
+/// One cell's value before and after a change.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Change<T> {
+    pub x: i64,
+    pub y: i64,
+    pub before: T,
+    pub after: T,
+}
+
+impl<T: Copy> Change<T> {
+    /// The change that takes the cell back.
+    pub fn reversed(self) -> Self {
+        Self {
+            before: self.after,
+            after: self.before,
+            ..self
+        }
+    }
+}
+
+/// Edits that can be undone, and undone edits that can be redone. Changes are
+/// gathered into an open edit until [`History::commit`] closes it, so a whole
+/// brush stroke undoes at once.
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    done: VecDeque<Vec<Change<T>>>,
+    undone: Vec<Vec<Change<T>>>,
+    open: Vec<Change<T>>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: vec![],
+            open: vec![],
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> History<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a change to the open edit. Changes that leave the cell as it was
+    /// are ignored.
+    pub fn record(&mut self, x: i64, y: i64, before: T, after: T) {
+        if before != after {
+            self.open.push(Change { x, y, before, after });
+        }
+    }
+
+    /// Closes the open edit, making it the one [`History::undo`] takes back
+    /// next. Anything undone can no longer be redone. Does nothing if the
+    /// open edit changed nothing.
+    pub fn commit(&mut self) {
+        if self.open.is_empty() {
+            return;
+        }
+        self.done.push_back(std::mem::take(&mut self.open));
+        if self.done.len() > MAX_EDITS {
+            self.done.pop_front();
+        }
+        self.undone.clear();
+    }
+
+    /// Takes back the last edit, committing the open one first. Returns the
+    /// changes to make, in the order to make them.
+    pub fn undo(&mut self) -> Option<Vec<Change<T>>> {
+        self.commit();
+        let edit = self.done.pop_back()?;
+        let changes = edit.iter().rev().map(|change| change.reversed()).collect();
+        self.undone.push(edit);
+        Some(changes)
+    }
+
+    /// Makes the last undone edit again. Returns the changes to make, in the
+    /// order to make them.
+    pub fn redo(&mut self) -> Option<Vec<Change<T>>> {
+        if !self.open.is_empty() {
+            return None;
+        }
+        let edit = self.undone.pop()?;
+        let changes = edit.clone();
+        self.done.push_back(edit);
+        Some(changes)
+    }
+
+    /// Whether changes have been recorded since the last commit.
+    pub fn is_open(&self) -> bool {
+        !self.open.is_empty()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty() || !self.open.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty() && self.open.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_whole_edits() {
+        let mut history = History::new();
+        history.record(0, 0, 0u8, 1);
+        history.record(1, 0, 0, 1);
+        history.commit();
+        history.record(0, 0, 1, 2);
+        assert!(history.is_open());
+
+        // undoing commits the open edit first, then takes it back
+        let undone = history.undo().unwrap();
+        assert_eq!(undone, vec![Change { x: 0, y: 0, before: 2, after: 1 }]);
+        let undone = history.undo().unwrap();
+        assert_eq!(undone, vec![Change { x: 1, y: 0, before: 1, after: 0 }, Change { x: 0, y: 0, before: 1, after: 0 }]);
+        assert!(history.undo().is_none());
+
+        let redone = history.redo().unwrap();
+        assert_eq!(redone, vec![Change { x: 0, y: 0, before: 0, after: 1 }, Change { x: 1, y: 0, before: 0, after: 1 }]);
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn changing_nothing_is_not_an_edit() {
+        let mut history = History::new();
+        history.record(3, 4, 1u8, 1);
+        history.commit();
+        assert!(!history.can_undo());
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn a_new_edit_forgets_what_was_undone() {
+        let mut history = History::new();
+        history.record(0, 0, 0u8, 1);
+        history.commit();
+        history.undo();
+        history.record(5, 5, 0, 1);
+        assert!(!history.can_redo());
+        assert!(history.redo().is_none());
+        history.commit();
+        assert!(history.redo().is_none());
+        assert_eq!(history.undo().unwrap()[0].x, 5);
+    }
+
+    #[test]
+    fn keeps_only_the_latest_edits() {
+        let mut history = History::new();
+        for i in 0..MAX_EDITS as i64 + 10 {
+            history.record(i, 0, 0u8, 1);
+            history.commit();
+        }
+        let mut undone = vec![];
+        while let Some(changes) = history.undo() {
+            undone.push(changes[0].x);
+        }
+        assert_eq!(undone.len(), MAX_EDITS);
+        assert_eq!(undone.last(), Some(&10));
+    }
+
+    #[test]
+    fn reversing_swaps_before_and_after() {
+        let change = Change { x: -2, y: 7, before: 0u8, after: 3 };
+        assert_eq!(change.reversed(), Change { x: -2, y: 7, before: 3, after: 0 });
+        assert_eq!(change.reversed().reversed(), change);
+    }
+}
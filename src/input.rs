@@ -1,7 +1,13 @@
 use bevy::{prelude::*, time::FixedTimestep};
+use crate::simulation::BoardSize;
+use crate::ui::RuleInput;
 
 const CAMERA_MOVE_SPEED: f32 = 25.0;
 const CAMERA_ZOOM_SPEED: f32 = 1.0;
+const START_SCALE: f32 = 3.5;
+/// Furthest the camera zooms out, where a pixel spans over a thousand cells
+/// each way.
+const MAX_SCALE: f32 = 65536.0;
 
 #[derive(Component)]
 pub struct MainCamera;
@@ -27,16 +33,19 @@ impl Plugin for InputPlugin {
 }
 
 fn setup(
-    mut commands: Commands
+    mut commands: Commands,
+    size: Res<BoardSize>,
 ) {
+    // a little left of and above the middle, clear of the menu
+    let centre = size.centre() + Vec2::new(-30.0, 80.0);
     commands
         .spawn(Camera2dBundle {
             projection: OrthographicProjection {
-                scale: 3.5,
+                scale: START_SCALE,
                 ..default()
             },
             transform: Transform {
-                translation: Vec3::new(1570.0, 1680.0, 0.0),
+                translation: centre.extend(0.0),
                 ..default()
             },
             ..default()
@@ -49,25 +58,29 @@ fn setup(
 }
 
 fn camera_move(
-    mut query: Query<(&mut Transform, &mut Movement), With<MainCamera>>,
+    mut query: Query<(&mut Transform, &mut Movement, &OrthographicProjection), With<MainCamera>>,
     keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
 ) {
+    // keystrokes belong to the rule while one is being typed
+    let pressed = |key| !rule_input.is_editing() && keyboard_input.pressed(key);
+
     let mut direction = Vec3::new(0.0, 0.0, 0.0);
-    if keyboard_input.pressed(KeyCode::W) {
+    if pressed(KeyCode::W) {
         direction.y += 1.7;
     }
-    if keyboard_input.pressed(KeyCode::S) {
+    if pressed(KeyCode::S) {
         direction.y -= 1.7;
     }
-    if keyboard_input.pressed(KeyCode::A) {
+    if pressed(KeyCode::A) {
         direction.x -= 1.7;
     }
-    if keyboard_input.pressed(KeyCode::D) {
+    if pressed(KeyCode::D) {
         direction.x += 1.7;
     }
     let direction = direction.normalize_or_zero();
 
-    let (mut transform, mut movement) = query.single_mut();
+    let (mut transform, mut movement, projection) = query.single_mut();
 
     movement.plane_speed = (movement.plane_speed + direction)
         .clamp(
@@ -75,23 +88,27 @@ fn camera_move(
             Vec3::new(CAMERA_MOVE_SPEED, CAMERA_MOVE_SPEED, CAMERA_MOVE_SPEED),
         );
 
-    if keyboard_input.pressed(KeyCode::R) {
+    if pressed(KeyCode::R) {
         movement.plane_speed = Vec3::new(0.0, 0.0, 0.0);
     }
 
-    transform.translation += movement.plane_speed;
+    // pan as fast across the screen at any zoom
+    transform.translation += movement.plane_speed * projection.scale / START_SCALE;
 }
 
 fn camera_zoom(
     mut camera: Query<(&mut Movement, &mut OrthographicProjection), With<MainCamera>>,
     keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
 ) {
+    let pressed = |key| !rule_input.is_editing() && keyboard_input.pressed(key);
+
     let mut zoom_direction = 0.0;
 
-    if keyboard_input.pressed(KeyCode::Q) {
+    if pressed(KeyCode::Q) {
         zoom_direction = 0.01;
     }
-    if keyboard_input.pressed(KeyCode::E) {
+    if pressed(KeyCode::E) {
         zoom_direction -= 0.01;
     }
 
@@ -99,9 +116,10 @@ fn camera_zoom(
     movement.zoom_speed = (movement.zoom_speed + zoom_direction)
         .clamp(-CAMERA_ZOOM_SPEED, CAMERA_ZOOM_SPEED);
 
-    orto_proj.scale = (orto_proj.scale + movement.zoom_speed).clamp(1.0, 6.0);
+    // zoom by a share of the current scale, so it feels the same at any depth
+    orto_proj.scale = (orto_proj.scale * (1.0 + movement.zoom_speed / START_SCALE)).clamp(1.0, MAX_SCALE);
 
-    if (orto_proj.scale - 1.0).abs() < 0.0001 || (orto_proj.scale - 6.0).abs() < 0.0001 || keyboard_input.pressed(KeyCode::R) {
+    if (orto_proj.scale - 1.0).abs() < 0.0001 || (orto_proj.scale - MAX_SCALE).abs() < 0.0001 || pressed(KeyCode::R) {
         movement.zoom_speed = 0.0;
     }
 }
\ No newline at end of file
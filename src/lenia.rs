@@ -0,0 +1,327 @@
+//! Lenia: a continuous automaton where every cell holds a value between 0
+//! and 1 and grows or shrinks with a smooth function of its weighted
+//! neighbourhood.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::topology::Topology;
+
+/// Largest kernel radius a rule may ask for.
+pub const MAX_RADIUS: u8 = 50;
+
+/// A single-ring Lenia rule. The kernel is a smooth bump over the ring of
+/// cells up to `radius` away, and the growth function is a Gaussian centred
+/// on `mu` with width `sigma`, scaled to -1..1. Each step moves a cell by
+/// `dt` times its growth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeniaRule {
+    radius: u8,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    /// `(dx, dy, weight)` for every cell under the kernel, weights summing to 1.
+    kernel: Vec<(i32, i32, f32)>,
+}
+
+impl LeniaRule {
+    pub fn new(radius: u8, mu: f32, sigma: f32, dt: f32) -> Result<Self, LeniaParseError> {
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(LeniaParseError::InvalidRadius(radius.to_string()));
+        }
+        if sigma <= 0.0 || !sigma.is_finite() {
+            return Err(LeniaParseError::InvalidValue("sigma", sigma.to_string()));
+        }
+        if !(dt > 0.0 && dt <= 1.0) {
+            return Err(LeniaParseError::InvalidValue("dt", dt.to_string()));
+        }
+        if !mu.is_finite() {
+            return Err(LeniaParseError::InvalidValue("mu", mu.to_string()));
+        }
+
+        let r = radius as i32;
+        let mut kernel = vec![];
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+                if distance > 0.0 && distance < 1.0 {
+                    let weight = (4.0 - 1.0 / (distance * (1.0 - distance))).exp();
+                    kernel.push((dx, dy, weight));
+                }
+            }
+        }
+        let total: f32 = kernel.iter().map(|&(_, _, w)| w).sum();
+        for (_, _, weight) in &mut kernel {
+            *weight /= total;
+        }
+
+        Ok(Self {
+            radius,
+            mu,
+            sigma,
+            dt,
+            kernel,
+        })
+    }
+
+    /// Orbium, the best known Lenia glider: `R13,mu0.15,sigma0.015,dt0.1`.
+    pub fn orbium() -> Self {
+        Self::new(13, 0.15, 0.015, 0.1).unwrap()
+    }
+
+    pub fn radius(&self) -> u8 {
+        self.radius
+    }
+
+    /// How fast a cell with neighbourhood potential `u` grows, from -1 to 1.
+    pub fn growth(&self, u: f32) -> f32 {
+        let d = (u - self.mu) / self.sigma;
+        2.0 * (-d * d / 2.0).exp() - 1.0
+    }
+}
+
+impl Default for LeniaRule {
+    fn default() -> Self {
+        Self::orbium()
+    }
+}
+
+impl fmt::Display for LeniaRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Lenia:R{},mu{},sigma{},dt{}",
+            self.radius, self.mu, self.sigma, self.dt
+        )
+    }
+}
+
+impl FromStr for LeniaRule {
+    type Err = LeniaParseError;
+
+    /// Accepts `Lenia` followed by any of `R`, `mu`, `sigma` and `dt`, e.g.
+    /// `Lenia:R13,mu0.15,sigma0.015,dt0.1`. Missing fields are Orbium's.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let fields = s
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("lenia"))
+            .map(|_| s[5..].trim_start_matches(':'))
+            .ok_or_else(|| LeniaParseError::UnknownFormat(s.to_string()))?;
+
+        let default = Self::orbium();
+        let (mut radius, mut mu, mut sigma, mut dt) = (default.radius, default.mu, default.sigma, default.dt);
+        for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let split = field.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(field.len());
+            let (key, value) = field.split_at(split);
+            let number = || {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| LeniaParseError::InvalidField(field.to_string()))
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "r" => {
+                    radius = value
+                        .parse()
+                        .map_err(|_| LeniaParseError::InvalidRadius(value.to_string()))?
+                }
+                "mu" | "m" => mu = number()?,
+                "sigma" | "s" => sigma = number()?,
+                "dt" | "t" => dt = number()?,
+                _ => return Err(LeniaParseError::InvalidField(field.to_string())),
+            }
+        }
+        Self::new(radius, mu, sigma, dt)
+    }
+}
+
+/// A board of continuous cell values between 0 and 1. What lies outside the
+/// board is decided by its [`Topology`]; by default everything there is 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    width: i32,
+    height: i32,
+    values: Vec<f32>,
+    topology: Topology,
+}
+
+impl Field {
+    pub fn new(width: i32, height: i32) -> Self {
+        assert!(width > 0 && height > 0, "field dimensions must be positive");
+        Self {
+            width,
+            height,
+            values: vec![0.0; (width * height) as usize],
+            topology: Topology::default(),
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The cell at `(x, y)`. Coordinates off the board are carried back onto
+    /// it by the field's topology.
+    pub fn get(&self, x: i32, y: i32) -> f32 {
+        match self.topology.resolve(x, y, self.width, self.height) {
+            Some((x, y)) => self.values[(x + y * self.width) as usize],
+            None => 0.0,
+        }
+    }
+
+    /// Sets the cell at `(x, y)`, clamped to 0..=1. Returns `false` if it
+    /// lies outside the board.
+    pub fn set(&mut self, x: i32, y: i32, value: f32) -> bool {
+        match self.index(x, y) {
+            Some(i) => {
+                self.values[i] = value.clamp(0.0, 1.0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sum of every cell value.
+    pub fn mass(&self) -> f32 {
+        self.values.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.values.fill(0.0);
+    }
+
+    /// Advances the field by one step of `rule`: convolve with the kernel,
+    /// apply the growth function and clip back to 0..=1.
+    pub fn step(&mut self, rule: &LeniaRule) {
+        let mut next = Vec::with_capacity(self.values.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let potential: f32 = rule
+                    .kernel
+                    .iter()
+                    .map(|&(dx, dy, weight)| weight * self.get(x + dx, y + dy))
+                    .sum();
+                let value = self.get(x, y) + rule.dt * rule.growth(potential);
+                next.push(value.clamp(0.0, 1.0));
+            }
+        }
+        self.values = next;
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            Some((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeniaParseError {
+    UnknownFormat(String),
+    InvalidField(String),
+    InvalidRadius(String),
+    InvalidValue(&'static str, String),
+}
+
+impl fmt::Display for LeniaParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeniaParseError::UnknownFormat(s) => {
+                write!(f, "'{s}' is not a Lenia rule, expected something like Lenia:R13,mu0.15,sigma0.015,dt0.1")
+            }
+            LeniaParseError::InvalidField(s) => write!(f, "'{s}' is not a valid Lenia field"),
+            LeniaParseError::InvalidRadius(s) => {
+                write!(f, "'{s}' is not a kernel radius, expected 1 to {MAX_RADIUS}")
+            }
+            LeniaParseError::InvalidValue(name, value) => write!(f, "{value} is not a usable {name}"),
+        }
+    }
+}
+
+impl std::error::Error for LeniaParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_is_a_normalised_ring() {
+        let rule = LeniaRule::orbium();
+        let total: f32 = rule.kernel.iter().map(|&(_, _, w)| w).sum();
+        assert!((total - 1.0).abs() < 1e-4);
+        let weight = |x, y| rule.kernel.iter().find(|&&(dx, dy, _)| (dx, dy) == (x, y)).map(|&(_, _, w)| w);
+        assert_eq!(weight(0, 0), None);
+        assert_eq!(weight(13, 0), None);
+        assert_eq!(weight(5, 3), weight(-3, 5));
+        assert!(weight(6, 0) > weight(1, 0) && weight(6, 0) > weight(12, 0));
+    }
+
+    #[test]
+    fn growth_peaks_at_mu() {
+        let rule = LeniaRule::orbium();
+        assert!((rule.growth(0.15) - 1.0).abs() < 1e-6);
+        assert!((rule.growth(0.0) + 1.0).abs() < 1e-6);
+        assert!(rule.growth(0.16) < 1.0 && rule.growth(0.16) > 0.0);
+        assert!((rule.growth(0.14) - rule.growth(0.16)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn even_fields_move_together() {
+        let rule = LeniaRule::orbium();
+        let mut empty = Field::new(30, 30);
+        empty.step(&rule);
+        assert_eq!(empty.mass(), 0.0);
+
+        let mut field = Field::new(30, 30);
+        field.set_topology(Topology::Torus);
+        for y in 0..30 {
+            for x in 0..30 {
+                field.set(x, y, 0.15);
+            }
+        }
+        field.step(&rule);
+        for (x, y) in [(0, 0), (29, 0), (14, 17)] {
+            assert!((field.get(x, y) - 0.25).abs() < 1e-4, "{}", field.get(x, y));
+        }
+    }
+
+    #[test]
+    fn values_are_kept_between_zero_and_one() {
+        let mut field = Field::new(4, 4);
+        assert!(field.set(1, 1, 3.0));
+        assert!(!field.set(4, 1, 0.5));
+        assert_eq!(field.get(1, 1), 1.0);
+        assert_eq!(field.get(-1, 1), 0.0);
+    }
+
+    #[test]
+    fn parses_and_prints_rules() {
+        assert_eq!("Lenia".parse::<LeniaRule>(), Ok(LeniaRule::orbium()));
+        assert_eq!("lenia:R13,mu0.15".parse::<LeniaRule>(), Ok(LeniaRule::orbium()));
+        let rule: LeniaRule = "Lenia:R10,m0.2,s0.03,t0.05".parse().unwrap();
+        assert_eq!(rule.to_string(), "Lenia:R10,mu0.2,sigma0.03,dt0.05");
+        assert_eq!(rule.to_string().parse::<LeniaRule>(), Ok(rule));
+        assert_eq!("Lenia:R0".parse::<LeniaRule>(), Err(LeniaParseError::InvalidRadius("0".into())));
+        assert_eq!("Lenia:dt2".parse::<LeniaRule>(), Err(LeniaParseError::InvalidValue("dt", "2".into())));
+        assert_eq!("Lenia:q3".parse::<LeniaRule>(), Err(LeniaParseError::InvalidField("q3".into())));
+        assert!("Lenia:dtNaN".parse::<LeniaRule>().is_err());
+        assert!("Lenia:dt-inf".parse::<LeniaRule>().is_err());
+        assert!(LeniaRule::new(13, 0.15, 0.015, f32::NAN).is_err());
+        assert!(LeniaRule::new(13, 0.15, f32::NAN, 0.1).is_err());
+        assert!(LeniaRule::new(13, f32::NAN, 0.015, 0.1).is_err());
+        assert!(matches!("Lenis".parse::<LeniaRule>(), Err(LeniaParseError::UnknownFormat(_))));
+    }
+}
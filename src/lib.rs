@@ -0,0 +1,21 @@
+//! Headless Game of Life engine. Nothing in here depends on Bevy, so it can be
+//! driven from tests, CI or other tools; the app only mirrors it into sprites.
+
+pub mod automaton;
+pub mod bitlife;
+pub mod census;
+pub mod elementary;
+pub mod fate;
+pub mod grid;
+pub mod hashlife;
+pub mod hensel;
+pub mod history;
+pub mod lenia;
+pub mod neighbourhood;
+pub mod noise;
+pub mod rule;
+pub mod ruletable;
+pub mod session;
+pub mod timeline;
+pub mod topology;
+pub mod universe;
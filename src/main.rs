@@ -5,11 +5,32 @@ mod simulation;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::prelude::*;
 use bevy_editor_pls::prelude::*;
+use game_of_life::topology::Topology;
 use crate::input::InputPlugin;
-use crate::simulation::SimulationPlugin;
+use crate::simulation::{fresh_seed, RuleLine, SimulationPlugin};
 use crate::ui::MainMenuPlugin;
 
 fn main() {
+    let arg = |name: &str| std::env::args().skip_while(|arg| arg != name).nth(1);
+    let line = RuleLine::parse(&arg("--rule").unwrap_or_else(|| "B3/S23".into())).unwrap_or_else(|e| {
+        eprintln!("Invalid --rule: {e}");
+        std::process::exit(2);
+    });
+    let seed = match arg("--seed") {
+        Some(seed) => seed.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid --seed: '{seed}' is not a number");
+            std::process::exit(2);
+        }),
+        None => line.seed.unwrap_or_else(fresh_seed),
+    };
+    let size = match arg("--size") {
+        Some(size) => size.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid --size: {e}");
+            std::process::exit(2);
+        }),
+        None => line.size.unwrap_or_default(),
+    };
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
@@ -25,7 +46,12 @@ fn main() {
         //.insert_resource(WinitSettings::desktop_app())
         .add_plugin(MainMenuPlugin)
         .add_plugin(InputPlugin)
-        .add_plugin(SimulationPlugin)
-        //.add_plugin(GameOfLife)
+        .add_plugin(SimulationPlugin {
+            rule: line.rule,
+            noise: line.noise,
+            seed,
+            topology: line.topology.unwrap_or(Topology::Plane),
+            size,
+        })
         .run();
 }
@@ -0,0 +1,255 @@
+use crate::grid::{Grid, ALIVE};
+
+/// Largest neighbourhood range a rule may ask for.
+pub const MAX_RANGE: u8 = 10;
+
+/// Which cells around a cell count as its neighbours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The `(2r+1) x (2r+1)` box around the cell.
+    Moore(u8),
+    /// Cells within Manhattan distance `r` of the cell.
+    VonNeumann(u8),
+    /// Cells within `r` steps on a hexagonal grid. Hexes are stored skewed on
+    /// the square grid, as Golly does: of the eight Moore neighbours, the ones
+    /// at `(1, -1)` and `(-1, 1)` are not adjacent.
+    Hexagonal(u8),
+    /// An arbitrary set of `(dx, dy)` offsets; see [`Neighbourhood::custom`].
+    Custom(Vec<(i8, i8)>),
+}
+
+impl Neighbourhood {
+    /// Builds a neighbourhood from offsets relative to the cell. The cell
+    /// itself and duplicates are dropped.
+    pub fn custom(offsets: impl IntoIterator<Item = (i8, i8)>) -> Self {
+        let mut offsets: Vec<_> = offsets.into_iter().filter(|&o| o != (0, 0)).collect();
+        offsets.sort_unstable_by_key(|&(dx, dy)| (dy, dx));
+        offsets.dedup();
+        Neighbourhood::Custom(offsets)
+    }
+
+    pub fn range(&self) -> u8 {
+        match self {
+            Neighbourhood::Moore(r) | Neighbourhood::VonNeumann(r) | Neighbourhood::Hexagonal(r) => *r,
+            Neighbourhood::Custom(offsets) => offsets
+                .iter()
+                .map(|&(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Number of neighbours, not counting the cell itself.
+    pub fn size(&self) -> usize {
+        let r = self.range() as usize;
+        match self {
+            Neighbourhood::Moore(_) => (2 * r + 1) * (2 * r + 1) - 1,
+            Neighbourhood::VonNeumann(_) => 2 * r * (r + 1),
+            Neighbourhood::Hexagonal(_) => 3 * r * (r + 1),
+            Neighbourhood::Custom(offsets) => offsets.len(),
+        }
+    }
+
+    pub fn contains(&self, dx: i32, dy: i32) -> bool {
+        let r = self.range() as i32;
+        if (dx, dy) == (0, 0) {
+            return false;
+        }
+        match self {
+            Neighbourhood::Moore(_) => dx.abs() <= r && dy.abs() <= r,
+            Neighbourhood::VonNeumann(_) => dx.abs() + dy.abs() <= r,
+            Neighbourhood::Hexagonal(_) => dx.abs() <= r && dy.abs() <= r && (dx - dy).abs() <= r,
+            Neighbourhood::Custom(offsets) => offsets.iter().any(|&(x, y)| (x as i32, y as i32) == (dx, dy)),
+        }
+    }
+
+    /// Every `(dx, dy)` offset in the neighbourhood, row by row.
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        let r = self.range() as i32;
+        let mut offsets = vec![];
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if self.contains(dx, dy) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+
+    /// Live neighbour count of every cell on `grid`, row by row. When
+    /// `include_centre` is set a live cell also counts itself.
+    ///
+    /// The built-in shapes are counted from prefix sums over the board rather
+    /// than visiting every neighbour, so large ranges cost little more than
+    /// range 1. Custom masks visit each offset.
+    pub fn count_live(&self, grid: &Grid, include_centre: bool) -> Vec<u16> {
+        let (w, h) = (grid.width(), grid.height());
+        let r = self.range() as i32;
+        let sums = PrefixSums::new(grid, r);
+        let offsets = match self {
+            Neighbourhood::Custom(_) => self.offsets(),
+            _ => vec![],
+        };
+
+        let mut counts = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                let alive = grid.get(x, y) == ALIVE;
+                let count = match self {
+                    Neighbourhood::Moore(_) => sums.rect(x - r, y - r, x + r, y + r) - alive as u32,
+                    Neighbourhood::VonNeumann(_) => {
+                        let count: u32 = (-r..=r)
+                            .map(|dy| {
+                                let reach = r - dy.abs();
+                                sums.row(y + dy, x - reach, x + reach)
+                            })
+                            .sum();
+                        count - alive as u32
+                    }
+                    Neighbourhood::Hexagonal(_) => {
+                        let count: u32 = (-r..=r)
+                            .map(|dy| sums.row(y + dy, x + (dy - r).max(-r), x + (dy + r).min(r)))
+                            .sum();
+                        count - alive as u32
+                    }
+                    Neighbourhood::Custom(_) => offsets
+                        .iter()
+                        .filter(|&&(dx, dy)| grid.get(x + dx, y + dy) == ALIVE)
+                        .count() as u32,
+                };
+                counts.push((count + (include_centre && alive) as u32) as u16);
+            }
+        }
+        counts
+    }
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Self {
+        Neighbourhood::Moore(1)
+    }
+}
+
+/// Row-wise and two-dimensional prefix sums of live cells over the board and
+/// a margin of `pad` cells around it, filled in through the board's topology.
+/// Queries beyond the margin are clipped.
+struct PrefixSums {
+    pad: i32,
+    width: i32,
+    height: i32,
+    rows: Vec<u32>,
+    table: Vec<u32>,
+}
+
+impl PrefixSums {
+    fn new(grid: &Grid, pad: i32) -> Self {
+        let (w, h) = (grid.width() + 2 * pad, grid.height() + 2 * pad);
+        let stride = (w + 1) as usize;
+        let mut rows = vec![0; stride * h as usize];
+        let mut table = vec![0; stride * (h + 1) as usize];
+        for y in 0..h as usize {
+            for x in 0..w as usize {
+                let alive = (grid.get(x as i32 - pad, y as i32 - pad) == ALIVE) as u32;
+                rows[y * stride + x + 1] = rows[y * stride + x] + alive;
+                table[(y + 1) * stride + x + 1] = table[y * stride + x + 1] + rows[y * stride + x + 1];
+            }
+        }
+        Self {
+            pad,
+            width: w,
+            height: h,
+            rows,
+            table,
+        }
+    }
+
+    /// Live cells in row `y` between columns `x0` and `x1`, inclusive.
+    fn row(&self, y: i32, x0: i32, x1: i32) -> u32 {
+        let (y, x0, x1) = (y + self.pad, x0 + self.pad, x1 + self.pad);
+        if y < 0 || y >= self.height {
+            return 0;
+        }
+        let (x0, x1) = (x0.max(0), (x1 + 1).min(self.width));
+        if x0 >= x1 {
+            return 0;
+        }
+        let base = (y * (self.width + 1)) as usize;
+        self.rows[base + x1 as usize] - self.rows[base + x0 as usize]
+    }
+
+    /// Live cells in the rectangle between `(x0, y0)` and `(x1, y1)`, inclusive.
+    fn rect(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> u32 {
+        let (x0, x1) = ((x0 + self.pad).max(0) as usize, (x1 + self.pad + 1).min(self.width) as usize);
+        let (y0, y1) = ((y0 + self.pad).max(0) as usize, (y1 + self.pad + 1).min(self.height) as usize);
+        if x0 >= x1 || y0 >= y1 {
+            return 0;
+        }
+        let stride = (self.width + 1) as usize;
+        self.table[y1 * stride + x1] + self.table[y0 * stride + x0]
+            - self.table[y0 * stride + x1]
+            - self.table[y1 * stride + x0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::soup;
+    use crate::topology::Topology;
+
+    #[test]
+    fn sizes_match_offsets() {
+        for r in 1..=4 {
+            for (neighbourhood, size) in [
+                (Neighbourhood::Moore(r), [8, 24, 48, 80]),
+                (Neighbourhood::VonNeumann(r), [4, 12, 24, 40]),
+                (Neighbourhood::Hexagonal(r), [6, 18, 36, 60]),
+            ] {
+                assert_eq!(neighbourhood.size(), size[r as usize - 1], "{neighbourhood:?}");
+                assert_eq!(neighbourhood.offsets().len(), neighbourhood.size(), "{neighbourhood:?}");
+            }
+        }
+        let hex = Neighbourhood::Hexagonal(1).offsets();
+        assert!(hex.contains(&(1, 1)) && hex.contains(&(-1, -1)));
+        assert!(!hex.contains(&(1, -1)) && !hex.contains(&(-1, 1)));
+    }
+
+    #[test]
+    fn custom_drops_the_centre_and_duplicates() {
+        let custom = Neighbourhood::custom([(0, 0), (2, -1), (0, 1), (2, -1)]);
+        assert_eq!(custom, Neighbourhood::Custom(vec![(2, -1), (0, 1)]));
+        assert_eq!((custom.range(), custom.size()), (2, 2));
+    }
+
+    #[test]
+    fn prefix_sums_count_the_same_as_visiting_every_neighbour() {
+        let shapes = [
+            Neighbourhood::Moore(1),
+            Neighbourhood::Moore(3),
+            Neighbourhood::VonNeumann(2),
+            Neighbourhood::Hexagonal(1),
+            Neighbourhood::Hexagonal(3),
+            Neighbourhood::custom([(-2, 1), (0, 2), (1, 0), (1, -1)]),
+        ];
+        for topology in [Topology::Dead, Topology::Torus, Topology::Klein, Topology::Mirror] {
+            let grid = soup(13, 9, 7, 2).with_topology(topology);
+            for neighbourhood in &shapes {
+                for include_centre in [false, true] {
+                    let expected: Vec<u16> = grid
+                        .iter()
+                        .map(|(x, y, state)| {
+                            let around = neighbourhood.offsets().iter().filter(|&&(dx, dy)| grid.is_alive(x + dx, y + dy)).count();
+                            (around + (include_centre && state == ALIVE) as usize) as u16
+                        })
+                        .collect();
+                    assert_eq!(
+                        neighbourhood.count_live(&grid, include_centre),
+                        expected,
+                        "{neighbourhood:?} on {topology:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Seeded randomness for stochastic rules: births and survivals that only
+//! happen with some probability, and random cell flips every generation.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::grid::{CellState, Grid, Transition, ALIVE, EMPTY};
+
+/// SplitMix64. Small and the same on every platform, so a seed always
+/// replays the same run.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// The generator for one generation of a run seeded with `seed`. Reseeding
+    /// every generation means a run can be replayed from any saved generation,
+    /// not just the first.
+    pub fn for_generation(seed: u64, generation: u64) -> Self {
+        let mut rng = Self::new(seed ^ generation.wrapping_mul(0xd1b5_4a32_d192_ed03));
+        rng.next_u64();
+        rng
+    }
+
+    /// The seed for one region of a run seeded with `seed`, so that separately
+    /// stepped chunks of a universe do not all draw the same numbers.
+    pub fn region_seed(seed: u64, region: (i64, i64)) -> u64 {
+        let mut rng = Self::new(seed ^ (region.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        rng.state ^= (region.1 as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        rng.next_u64()
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// `true` with probability `p`.
+    pub fn chance(&mut self, p: f32) -> bool {
+        ((self.next_u64() >> 40) as f32 / (1u64 << 24) as f32) < p
+    }
+}
+
+/// How a rule's generations are disturbed. A birth (empty to alive) only
+/// happens with probability `birth` and a survival (alive to alive) with
+/// probability `survival`; a live cell that misses its survival dies as the
+/// rule would have killed it. Afterwards every cell flips with probability
+/// `mutation`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Noise {
+    pub birth: f32,
+    pub survival: f32,
+    pub mutation: f32,
+}
+
+impl Noise {
+    /// Whether the noise changes anything at all.
+    pub fn is_random(&self) -> bool {
+        self.birth < 1.0 || self.survival < 1.0 || self.mutation > 0.0
+    }
+
+    /// `rule` with this noise laid over it for generation `generation` of a
+    /// run seeded with `seed`. `states` is the rule's state count, which
+    /// decides how a cell that misses its survival dies.
+    pub fn over<'a, T: Transition>(&self, rule: &'a T, states: u8, seed: u64, generation: u64) -> Noisy<'a, T> {
+        Noisy {
+            rule,
+            noise: *self,
+            states,
+            seed,
+            generation,
+        }
+    }
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            birth: 1.0,
+            survival: 1.0,
+            mutation: 0.0,
+        }
+    }
+}
+
+/// Written as the fields that differ from no noise at all, e.g.
+/// `b0.5,m0.001`, or nothing.
+impl fmt::Display for Noise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [("b", self.birth, 1.0), ("s", self.survival, 1.0), ("m", self.mutation, 0.0)];
+        let mut first = true;
+        for (name, value, default) in fields {
+            if value != default {
+                write!(f, "{}{name}{value}", if first { "" } else { "," })?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Noise {
+    type Err = NoiseParseError;
+
+    /// Accepts comma-separated `b`, `s` and `m` probabilities, e.g.
+    /// `b0.9,s0.95,m0.001`. Missing fields add no noise.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut noise = Noise::default();
+        for field in s.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let mut chars = field.chars();
+            let slot = match chars.next().unwrap().to_ascii_lowercase() {
+                'b' => &mut noise.birth,
+                's' => &mut noise.survival,
+                'm' => &mut noise.mutation,
+                _ => return Err(NoiseParseError::InvalidField(field.to_string())),
+            };
+            *slot = chars
+                .as_str()
+                .parse::<f32>()
+                .ok()
+                .filter(|p| (0.0..=1.0).contains(p))
+                .ok_or_else(|| NoiseParseError::InvalidProbability(field.to_string()))?;
+        }
+        Ok(noise)
+    }
+}
+
+/// A rule with [`Noise`] laid over one of its generations; see [`Noise::over`].
+pub struct Noisy<'a, T> {
+    rule: &'a T,
+    noise: Noise,
+    states: u8,
+    seed: u64,
+    generation: u64,
+}
+
+impl<T: Transition> Transition for Noisy<'_, T> {
+    fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        let mut next = self.rule.next_states(grid);
+        if !self.noise.is_random() {
+            return next;
+        }
+
+        let mut rng = Rng::for_generation(self.seed, self.generation);
+        let dying = if self.states > 2 { ALIVE + 1 } else { EMPTY };
+        for ((_, _, before), after) in grid.iter().zip(next.iter_mut()) {
+            match (before, *after) {
+                (EMPTY, ALIVE) if !rng.chance(self.noise.birth) => *after = EMPTY,
+                (ALIVE, ALIVE) if !rng.chance(self.noise.survival) => *after = dying,
+                _ => {}
+            }
+            if rng.chance(self.noise.mutation) {
+                *after = if *after == EMPTY { ALIVE } else { EMPTY };
+            }
+        }
+        next
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoiseParseError {
+    InvalidField(String),
+    InvalidProbability(String),
+}
+
+impl fmt::Display for NoiseParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoiseParseError::InvalidField(s) => {
+                write!(f, "'{s}' is not a noise field, expected b, s or m followed by a probability")
+            }
+            NoiseParseError::InvalidProbability(s) => {
+                write!(f, "'{s}' does not give a probability from 0 to 1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NoiseParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::soup;
+    use crate::rule::Rule;
+
+    #[test]
+    fn a_seed_replays_the_same_numbers() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(42), draws(42));
+        assert_ne!(draws(42), draws(43));
+        assert_eq!(Rng::for_generation(5, 9).next_u64(), Rng::for_generation(5, 9).next_u64());
+        assert_ne!(Rng::for_generation(5, 9).next_u64(), Rng::for_generation(5, 10).next_u64());
+        assert_ne!(Rng::region_seed(5, (0, 1)), Rng::region_seed(5, (1, 0)));
+    }
+
+    #[test]
+    fn chances_come_up_about_as_often_as_asked() {
+        let mut rng = Rng::new(1);
+        assert!((0..1000).all(|_| !rng.chance(0.0)));
+        assert!((0..1000).all(|_| rng.chance(1.0)));
+        let hits = (0..20_000).filter(|_| rng.chance(0.25)).count();
+        assert!((4_600..5_400).contains(&hits), "{hits}");
+    }
+
+    #[test]
+    fn noisy_runs_replay_from_their_seed() {
+        let noise: Noise = "b0.8,s0.9,m0.01".parse().unwrap();
+        let life = Rule::life();
+        let run = |seed| {
+            let mut grid = soup(24, 24, 3, 2);
+            for generation in 0..10 {
+                grid.step(&noise.over(&life, 2, seed, generation));
+            }
+            grid
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn no_noise_leaves_the_rule_alone() {
+        let grid = soup(24, 24, 4, 2);
+        let life = Rule::life();
+        assert_eq!(Noise::default().over(&life, 2, 1, 0).next_states(&grid), life.next_states(&grid));
+    }
+
+    #[test]
+    fn certain_failure_stops_births_and_survivals() {
+        let grid = soup(24, 24, 5, 2);
+        let life = Rule::life();
+        let no_births: Noise = "b0".parse().unwrap();
+        let next = no_births.over(&life, 2, 1, 0).next_states(&grid);
+        assert!(grid.iter().zip(&next).all(|((_, _, before), &after)| before == ALIVE || after == EMPTY));
+
+        let no_survivals: Noise = "s0".parse().unwrap();
+        let brain = Rule::generations(&[2], &[2, 3], 3);
+        let next = no_survivals.over(&brain, 3, 1, 0).next_states(&grid);
+        assert!(grid.iter().zip(&next).all(|((_, _, before), &after)| before != ALIVE || after == 2));
+    }
+
+    #[test]
+    fn parses_and_prints_noise() {
+        let noise: Noise = "b0.5, m0.001".parse().unwrap();
+        assert_eq!(noise, Noise { birth: 0.5, survival: 1.0, mutation: 0.001 });
+        assert_eq!(noise.to_string(), "b0.5,m0.001");
+        assert_eq!("".parse::<Noise>(), Ok(Noise::default()));
+        assert!(!Noise::default().is_random() && noise.is_random());
+        assert_eq!("x0.5".parse::<Noise>(), Err(NoiseParseError::InvalidField("x0.5".into())));
+        assert_eq!("s1.5".parse::<Noise>(), Err(NoiseParseError::InvalidProbability("s1.5".into())));
+    }
+}
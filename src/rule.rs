@@ -0,0 +1,828 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use crate::bitlife::BitRule;
+use crate::grid::{CellState, Grid, Transition, ALIVE, EMPTY};
+use crate::hensel;
+use crate::neighbourhood::{Neighbourhood, MAX_RANGE};
+
+/// A Life-like rule: which neighbour counts give birth to an empty cell and
+/// which let a live cell survive.
+///
+/// Rules with more than two states are "Generations" rules: a live cell that
+/// fails to survive goes through `states - 2` dying states before it is empty
+/// again, and only empty cells can be born.
+///
+/// Larger than Life rules count over a bigger neighbourhood and may count the
+/// cell itself as well.
+///
+/// Isotropic non-totalistic rules look at which Moore neighbours are alive
+/// rather than how many; their tables are indexed by the configuration bits
+/// from [`hensel::configurations`] instead of by count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    states: u8,
+    neighbourhood: Neighbourhood,
+    include_centre: bool,
+    isotropic: bool,
+}
+
+impl Rule {
+    pub fn new(birth: &[u16], survival: &[u16]) -> Self {
+        Self::larger_than_life(Neighbourhood::default(), false, birth, survival)
+    }
+
+    pub fn generations(birth: &[u16], survival: &[u16], states: u8) -> Self {
+        assert!(states >= 2, "a rule needs at least two states");
+        Self {
+            states,
+            ..Self::new(birth, survival)
+        }
+    }
+
+    pub fn larger_than_life(
+        neighbourhood: Neighbourhood,
+        include_centre: bool,
+        birth: &[u16],
+        survival: &[u16],
+    ) -> Self {
+        let mut rule = Self {
+            birth: vec![],
+            survival: vec![],
+            states: 2,
+            neighbourhood,
+            include_centre,
+            isotropic: false,
+        };
+        rule.birth = vec![false; rule.max_count() as usize + 1];
+        rule.survival = rule.birth.clone();
+        for &n in birth {
+            rule.birth[n as usize] = true;
+        }
+        for &n in survival {
+            rule.survival[n as usize] = true;
+        }
+        rule
+    }
+
+    /// Conway's Game of Life, B3/S23.
+    pub fn life() -> Self {
+        Self::new(&[3], &[2, 3])
+    }
+
+    /// Number of cell states including empty and alive.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighbourhood(&self) -> &Neighbourhood {
+        &self.neighbourhood
+    }
+
+    /// Whether a live cell counts towards its own neighbour count.
+    pub fn includes_centre(&self) -> bool {
+        self.include_centre
+    }
+
+    /// Highest neighbour count the rule can see.
+    pub fn max_count(&self) -> u16 {
+        self.neighbourhood.size() as u16 + self.include_centre as u16
+    }
+
+    /// Whether the rule is isotropic non-totalistic, written with Hensel letters.
+    pub fn is_isotropic(&self) -> bool {
+        self.isotropic
+    }
+
+    /// What the rule needs to know about the neighbours of every cell on
+    /// `grid`, row by row: the live count, or the configuration bits for
+    /// isotropic rules.
+    pub fn neighbour_keys(&self, grid: &Grid) -> Vec<u16> {
+        if self.isotropic {
+            hensel::configurations(grid)
+        } else {
+            self.neighbourhood.count_live(grid, self.include_centre)
+        }
+    }
+
+    /// State of a cell in the next generation, given its current state and
+    /// its entry from [`Rule::neighbour_keys`].
+    pub fn next_state(&self, state: CellState, neighbours: u16) -> CellState {
+        match state {
+            EMPTY if self.is_birth(neighbours) => ALIVE,
+            EMPTY => EMPTY,
+            ALIVE if self.is_survival(neighbours) => ALIVE,
+            _ if state < self.states - 1 => state + 1,
+            _ => EMPTY,
+        }
+    }
+
+    /// The bit-parallel form of the rule, which stepping a whole grid uses
+    /// when there is one. See [`BitRule::new`] for the rules that have one.
+    pub fn bit_rule(&self) -> Option<BitRule> {
+        BitRule::new(self)
+    }
+
+    pub fn is_birth(&self, neighbours: u16) -> bool {
+        self.birth.get(neighbours as usize).copied().unwrap_or(false)
+    }
+
+    pub fn is_survival(&self, neighbours: u16) -> bool {
+        self.survival.get(neighbours as usize).copied().unwrap_or(false)
+    }
+
+    fn is_larger_than_life(&self) -> bool {
+        match self.neighbourhood {
+            Neighbourhood::Moore(1) | Neighbourhood::VonNeumann(1) | Neighbourhood::Hexagonal(1) => {
+                self.include_centre
+            }
+            _ => true,
+        }
+    }
+
+    /// Sizes the count tables to the neighbourhood, failing if the rule asks
+    /// for counts the neighbourhood can never reach.
+    fn fit_counts(&mut self) -> Result<(), RuleParseError> {
+        if self.isotropic {
+            return match self.neighbourhood {
+                Neighbourhood::Moore(1) => Ok(()),
+                _ => Err(RuleParseError::IsotropicNeighbourhood),
+            };
+        }
+        let max = self.max_count();
+        let highest = self
+            .birth
+            .iter()
+            .zip(&self.survival)
+            .rposition(|(&b, &s)| b || s)
+            .unwrap_or(0) as u16;
+        if highest > max {
+            return Err(RuleParseError::CountOutOfRange { count: highest, max });
+        }
+        self.birth.resize(max as usize + 1, false);
+        self.survival.resize(max as usize + 1, false);
+        Ok(())
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::life()
+    }
+}
+
+impl Transition for Rule {
+    fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        if let Some(bits) = self.bit_rule() {
+            return bits.next_states(grid);
+        }
+        let keys = self.neighbour_keys(grid);
+        grid.iter().zip(keys).map(|((_, _, state), key)| self.next_state(state, key)).collect()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_larger_than_life() {
+            let states = if self.states > 2 { self.states } else { 0 };
+            write!(f, "R{},C{},M{},S", self.neighbourhood.range(), states, self.include_centre as u8)?;
+            write_count_ranges(f, &self.survival)?;
+            write!(f, ",B")?;
+            write_count_ranges(f, &self.birth)?;
+            return match &self.neighbourhood {
+                Neighbourhood::Moore(_) => write!(f, ",NM"),
+                Neighbourhood::VonNeumann(_) => write!(f, ",NN"),
+                Neighbourhood::Hexagonal(_) => write!(f, ",NH"),
+                Neighbourhood::Custom(_) => write!(f, ",N@{}", mask_to_hex(&self.neighbourhood)),
+            };
+        }
+
+        if self.isotropic {
+            let classes = hensel::letter_classes();
+            write!(f, "B")?;
+            write_letter_counts(f, &self.birth, &classes)?;
+            write!(f, "/S")?;
+            write_letter_counts(f, &self.survival, &classes)?;
+        } else {
+            write!(f, "B")?;
+            for n in (0..9).filter(|&n| self.is_birth(n)) {
+                write!(f, "{n}")?;
+            }
+            write!(f, "/S")?;
+            for n in (0..9).filter(|&n| self.is_survival(n)) {
+                write!(f, "{n}")?;
+            }
+        }
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        match self.neighbourhood {
+            Neighbourhood::VonNeumann(_) => write!(f, "V"),
+            Neighbourhood::Hexagonal(_) => write!(f, "H"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Writes an isotropic table in Hensel notation, listing for each count the
+/// letters it applies to, or the ones it does not after a `-` when shorter.
+fn write_letter_counts(f: &mut fmt::Formatter<'_>, table: &[bool], classes: &[char; 256]) -> fmt::Result {
+    for (count, letters) in hensel::LETTERS.iter().enumerate() {
+        let configs: Vec<usize> = (0..256).filter(|c: &usize| c.count_ones() as usize == count).collect();
+        let included = |letter: char| configs.iter().any(|&c| classes[c] == letter && table[c]);
+        if letters.is_empty() {
+            if included(' ') {
+                write!(f, "{count}")?;
+            }
+            continue;
+        }
+        let on: String = letters.chars().filter(|&l| included(l)).collect();
+        let off: String = letters.chars().filter(|&l| !included(l)).collect();
+        match (on.is_empty(), off.is_empty()) {
+            (true, _) => {}
+            (false, true) => write!(f, "{count}")?,
+            _ if off.len() < on.len() => write!(f, "{count}-{off}")?,
+            _ => write!(f, "{count}{on}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the set counts as `a..b` runs separated by commas.
+fn write_count_ranges(f: &mut fmt::Formatter<'_>, table: &[bool]) -> fmt::Result {
+    let mut first = true;
+    let mut n = 0;
+    while n < table.len() {
+        if !table[n] {
+            n += 1;
+            continue;
+        }
+        let start = n;
+        while n + 1 < table.len() && table[n + 1] {
+            n += 1;
+        }
+        if !first {
+            write!(f, ",")?;
+        }
+        first = false;
+        if start == n {
+            write!(f, "{start}")?;
+        } else {
+            write!(f, "{start}..{n}")?;
+        }
+        n += 1;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleParseError {
+    Empty,
+    /// Neither `B.../S...`, the `survival/birth` form nor Larger than Life.
+    UnknownFormat(String),
+    /// A count that is not a single digit from 0 to 8.
+    InvalidCount(char),
+    DuplicateCount(char),
+    DuplicateSection(char),
+    /// The state count of a Generations rule is not a number from 2 to 255.
+    InvalidStates(String),
+    /// A Larger than Life field that could not be read.
+    InvalidField(String),
+    /// The neighbourhood range is not a number from 1 to `MAX_RANGE`.
+    InvalidRange(String),
+    /// A neighbour count higher than the neighbourhood allows.
+    CountOutOfRange { count: u16, max: u16 },
+    /// A Hensel letter that does not exist for the count it follows.
+    InvalidLetter { count: u8, letter: char },
+    DuplicateLetter { count: u8, letter: char },
+    /// Hensel letters are only defined on the range 1 Moore neighbourhood.
+    IsotropicNeighbourhood,
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleParseError::Empty => write!(f, "rule string is empty"),
+            RuleParseError::UnknownFormat(s) => {
+                write!(f, "'{s}' is not a rule, expected something like B3/S23, 23/3 or R5,C0,M1,S34..58,B34..45,NM")
+            }
+            RuleParseError::InvalidCount(c) => {
+                write!(f, "'{c}' is not a neighbour count, expected 0-8")
+            }
+            RuleParseError::DuplicateCount(c) => write!(f, "neighbour count {c} is listed twice"),
+            RuleParseError::DuplicateSection(c) => write!(f, "'{c}' section is given twice"),
+            RuleParseError::InvalidStates(s) => {
+                write!(f, "'{s}' is not a state count, expected a number from 2 to 255")
+            }
+            RuleParseError::InvalidField(s) => write!(f, "'{s}' is not a valid rule field"),
+            RuleParseError::InvalidRange(s) => {
+                write!(f, "'{s}' is not a neighbourhood range, expected 1 to {MAX_RANGE}")
+            }
+            RuleParseError::CountOutOfRange { count, max } => {
+                write!(f, "neighbour count {count} is more than the neighbourhood holds ({max})")
+            }
+            RuleParseError::InvalidLetter { count, letter } => match hensel::LETTERS[*count as usize] {
+                "" => write!(f, "count {count} takes no letters, found '{letter}'"),
+                letters => write!(f, "'{letter}' is not a letter for count {count}, expected one of {letters}"),
+            },
+            RuleParseError::DuplicateLetter { count, letter } => {
+                write!(f, "letter '{letter}' is listed twice for count {count}")
+            }
+            RuleParseError::IsotropicNeighbourhood => {
+                write!(f, "Hensel letters only work with the Moore neighbourhood")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    /// Accepts `B3/S23` (sections in either order, slash optional, any case)
+    /// and the older `S/B` form such as `23/3`. Generations rules add a state
+    /// count: `B2/S/C3` or `345/2/4`. Counts may be narrowed with Hensel
+    /// letters, e.g. `B2-a/S12` or `B3/S2-i34q`. A trailing `H` or `V` switches to the
+    /// hexagonal or von Neumann neighbourhood (`B2/S34H`). Larger than Life
+    /// rules use `R5,C0,M1,S34..58,B34..45,NM`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+
+        if s.as_bytes()[0].eq_ignore_ascii_case(&b'r') && s[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            return parse_larger_than_life(s);
+        }
+
+        let (s, neighbourhood) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('H') => (&s[..s.len() - 1], Neighbourhood::Hexagonal(1)),
+            Some('V') => (&s[..s.len() - 1], Neighbourhood::VonNeumann(1)),
+            _ => (s, Neighbourhood::Moore(1)),
+        };
+        let mut rule = parse_birth_survival(s)?;
+        rule.neighbourhood = neighbourhood;
+        rule.fit_counts()?;
+        Ok(rule)
+    }
+}
+
+/// Parses the B/S and S/B forms, with an optional Generations state count.
+fn parse_birth_survival(s: &str) -> Result<Rule, RuleParseError> {
+    if s.is_empty() {
+        return Err(RuleParseError::Empty);
+    }
+
+    let mut birth = None;
+    let mut survival = None;
+    let mut states = None;
+    if s.starts_with(|c: char| c.is_ascii_digit() || c == '/') {
+        let parts: Vec<&str> = s.split('/').collect();
+        match parts[..] {
+            [survival_counts, birth_counts] | [survival_counts, birth_counts, _] => {
+                survival = Some(parse_counts(survival_counts)?);
+                birth = Some(parse_counts(birth_counts)?);
+            }
+            _ => return Err(RuleParseError::UnknownFormat(s.to_string())),
+        }
+        if let [_, _, count] = parts[..] {
+            states = Some(parse_states(count)?);
+        }
+    } else {
+        // 'c' is also a Hensel letter, so a state count section has to start
+        // its own slash-separated part
+        for part in s.split('/').filter(|part| !part.is_empty()) {
+            let mut rest = part;
+            while !rest.is_empty() {
+                let mut chars = rest.chars();
+                let section = chars.next().unwrap().to_ascii_uppercase();
+                let body = chars.as_str();
+                match section {
+                    'B' | 'S' => {
+                        let end = body.find(['B', 'S', 'b', 's']).unwrap_or(body.len());
+                        let (counts, tail) = body.split_at(end);
+                        let slot = if section == 'B' { &mut birth } else { &mut survival };
+                        if slot.is_some() {
+                            return Err(RuleParseError::DuplicateSection(section));
+                        }
+                        *slot = Some(parse_counts(counts)?);
+                        rest = tail;
+                    }
+                    'C' | 'G' if rest.len() == part.len() => {
+                        if states.is_some() {
+                            return Err(RuleParseError::DuplicateSection(section));
+                        }
+                        states = Some(parse_states(body)?);
+                        rest = "";
+                    }
+                    _ => return Err(RuleParseError::UnknownFormat(s.to_string())),
+                }
+            }
+        }
+    }
+
+    let (birth, survival) = match (birth, survival) {
+        (Some(birth), Some(survival)) => (birth, survival),
+        _ => return Err(RuleParseError::UnknownFormat(s.to_string())),
+    };
+    let mut rule = Rule::new(&[], &[]);
+    rule.states = states.unwrap_or(2);
+    rule.isotropic = birth.has_letters || survival.has_letters;
+    if rule.isotropic {
+        rule.birth = birth.configs.to_vec();
+        rule.survival = survival.configs.to_vec();
+    } else {
+        for (counts, table) in [(birth, &mut rule.birth), (survival, &mut rule.survival)] {
+            for (config, _) in counts.configs.iter().enumerate().filter(|(_, &on)| on) {
+                table[(config as u8).count_ones() as usize] = true;
+            }
+        }
+    }
+    Ok(rule)
+}
+
+/// The neighbourhood configurations picked out by one B or S section.
+struct Counts {
+    configs: [bool; 256],
+    has_letters: bool,
+}
+
+fn parse_states(states: &str) -> Result<u8, RuleParseError> {
+    states
+        .parse::<u8>()
+        .ok()
+        .filter(|&n| n >= 2)
+        .ok_or_else(|| RuleParseError::InvalidStates(states.to_string()))
+}
+
+/// Reads counts such as `23`, or with Hensel letters `2-i34q`: a count on
+/// its own takes every configuration, letters after it take only those
+/// classes, and a `-` before the letters takes every class but those.
+fn parse_counts(counts: &str) -> Result<Counts, RuleParseError> {
+    let classes = hensel::letter_classes();
+    let mut parsed = Counts {
+        configs: [false; 256],
+        has_letters: false,
+    };
+    let mut seen = [false; 9];
+    let mut chars = counts.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c
+            .to_digit(10)
+            .filter(|&n| n <= 8)
+            .ok_or(RuleParseError::InvalidCount(c))? as u8;
+        if seen[count as usize] {
+            return Err(RuleParseError::DuplicateCount(c));
+        }
+        seen[count as usize] = true;
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            let letter = letter.to_ascii_lowercase();
+            if !hensel::LETTERS[count as usize].contains(letter) {
+                return Err(RuleParseError::InvalidLetter { count, letter });
+            }
+            if letters.contains(letter) {
+                return Err(RuleParseError::DuplicateLetter { count, letter });
+            }
+            letters.push(letter);
+        }
+        if negated && letters.is_empty() {
+            return Err(RuleParseError::InvalidCount('-'));
+        }
+        parsed.has_letters |= !letters.is_empty();
+
+        for config in (0..256).filter(|c: &usize| c.count_ones() == count as u32) {
+            let listed = letters.contains(classes[config]);
+            parsed.configs[config] = letters.is_empty() || listed != negated;
+        }
+    }
+    Ok(parsed)
+}
+
+/// Parses the Larger than Life form `Rr,Cc,Mm,Smin..max,Bmin..max,Nn`. The
+/// S and B fields may hold several counts or ranges (`S2..3,5,7-9`), in which
+/// case the extra ones follow as bare comma-separated fields.
+fn parse_larger_than_life(s: &str) -> Result<Rule, RuleParseError> {
+    let mut range = None;
+    let mut states = 2;
+    let mut include_centre = false;
+    let mut birth: Option<Vec<RangeInclusive<u16>>> = None;
+    let mut survival: Option<Vec<RangeInclusive<u16>>> = None;
+    let mut kind = "M";
+    let mut last_section = None;
+
+    for field in s.split(',').map(str::trim) {
+        let invalid = || RuleParseError::InvalidField(field.to_string());
+        let continued = !field.starts_with(|c: char| c.is_ascii_alphabetic());
+        let (section, value) = match (continued, last_section) {
+            (false, _) => (field[..1].to_ascii_uppercase().chars().next().unwrap(), &field[1..]),
+            (true, Some(section @ ('S' | 'B'))) if !field.is_empty() => (section, field),
+            _ => return Err(invalid()),
+        };
+        last_section = Some(section);
+
+        match section {
+            'R' => {
+                range = Some(
+                    value
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|r| (1..=MAX_RANGE).contains(r))
+                        .ok_or_else(|| RuleParseError::InvalidRange(value.to_string()))?,
+                );
+            }
+            'C' => {
+                states = match value.parse::<u8>() {
+                    Ok(0) => 2,
+                    _ => parse_states(value)?,
+                };
+            }
+            'M' => {
+                include_centre = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(invalid()),
+                };
+            }
+            'S' | 'B' => {
+                let slot = if section == 'S' { &mut survival } else { &mut birth };
+                if slot.is_some() && !continued {
+                    return Err(RuleParseError::DuplicateSection(section));
+                }
+                let ranges = slot.get_or_insert_with(Vec::new);
+                if !value.is_empty() {
+                    ranges.push(parse_count_range(value).ok_or_else(invalid)?);
+                }
+            }
+            'N' => kind = value,
+            _ => return Err(invalid()),
+        }
+    }
+
+    let (range, birth, survival) = match (range, birth, survival) {
+        (Some(range), Some(birth), Some(survival)) => (range, birth, survival),
+        _ => return Err(RuleParseError::UnknownFormat(s.to_string())),
+    };
+    let neighbourhood = match kind.to_ascii_uppercase().as_str() {
+        "M" => Neighbourhood::Moore(range),
+        "N" => Neighbourhood::VonNeumann(range),
+        "H" => Neighbourhood::Hexagonal(range),
+        _ => match kind.strip_prefix('@').and_then(|hex| mask_from_hex(hex, range)) {
+            Some(neighbourhood) => neighbourhood,
+            None => return Err(RuleParseError::InvalidField(format!("N{kind}"))),
+        },
+    };
+
+    let mut rule = Rule::larger_than_life(neighbourhood, include_centre, &[], &[]);
+    rule.states = states;
+    let max = rule.max_count();
+    for (ranges, table) in [(birth, &mut rule.birth), (survival, &mut rule.survival)] {
+        for counts in ranges {
+            if *counts.end() > max {
+                return Err(RuleParseError::CountOutOfRange { count: *counts.end(), max });
+            }
+            for n in counts {
+                table[n as usize] = true;
+            }
+        }
+    }
+    Ok(rule)
+}
+
+/// Reads `a..b`, `a-b` or a single count `a`.
+fn parse_count_range(value: &str) -> Option<RangeInclusive<u16>> {
+    let (start, end) = value
+        .split_once("..")
+        .or_else(|| value.split_once('-'))
+        .unwrap_or((value, value));
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start <= end).then_some(start..=end)
+}
+
+/// Reads a custom neighbourhood written as hex digits: one bit per cell of
+/// the `(2r+1) x (2r+1)` box, row by row from the top-left, most significant
+/// bit first, so the first row is `dy = r`. The bit for the cell itself is
+/// ignored.
+fn mask_from_hex(hex: &str, range: u8) -> Option<Neighbourhood> {
+    let side = 2 * range as i32 + 1;
+    let bits = (side * side) as usize;
+    if hex.len() != bits.div_ceil(4) {
+        return None;
+    }
+    let mut offsets = vec![];
+    for (i, digit) in hex.chars().enumerate() {
+        let digit = digit.to_digit(16)?;
+        for bit in 0..4 {
+            let n = i * 4 + bit;
+            if n < bits && digit & (8 >> bit) != 0 {
+                let (dx, dy) = (n as i32 % side - range as i32, range as i32 - n as i32 / side);
+                offsets.push((dx as i8, dy as i8));
+            }
+        }
+    }
+    Some(Neighbourhood::custom(offsets))
+}
+
+fn mask_to_hex(neighbourhood: &Neighbourhood) -> String {
+    let r = neighbourhood.range() as i32;
+    let side = 2 * r + 1;
+    let bits = (side * side) as usize;
+    let mut digits = vec![0u32; bits.div_ceil(4)];
+    for (dx, dy) in neighbourhood.offsets() {
+        let n = ((r - dy) * side + dx + r) as usize;
+        digits[n / 4] |= 8 >> (n % 4);
+    }
+    digits.iter().map(|&d| char::from_digit(d, 16).unwrap().to_ascii_uppercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::board;
+
+    fn glider() -> Grid {
+        board(&[".o......", "..o.....", "ooo.....", "........", "........", "........", "........", "........"])
+    }
+
+    #[test]
+    fn larger_than_life_rulestrings_round_trip() {
+        for s in ["R5,C0,M1,S34..58,B34..45,NM", "R2,C3,M0,S2..3,5,B3,NN", "R3,C0,M0,S1..4,B2,NH"] {
+            let rule: Rule = s.parse().unwrap();
+            assert_eq!(rule.to_string(), s);
+        }
+        let rule: Rule = "r2,c0,m1,s2-3,b3,nm".parse().unwrap();
+        assert_eq!(rule.neighbourhood(), &Neighbourhood::Moore(2));
+        assert!(rule.includes_centre());
+        assert_eq!(rule.to_string(), "R2,C0,M1,S2..3,B3,NM");
+    }
+
+    #[test]
+    fn rejects_bad_larger_than_life_rules() {
+        assert_eq!("R0,C0,M0,S1,B1,NM".parse::<Rule>(), Err(RuleParseError::InvalidRange("0".into())));
+        assert_eq!("R11,C0,M0,S1,B1,NM".parse::<Rule>(), Err(RuleParseError::InvalidRange("11".into())));
+        assert_eq!(
+            "R1,C0,M0,S9,B3,NM".parse::<Rule>(),
+            Err(RuleParseError::CountOutOfRange { count: 9, max: 8 })
+        );
+        assert_eq!("R1,C0,M2,S2,B3,NM".parse::<Rule>(), Err(RuleParseError::InvalidField("M2".into())));
+        assert!("R1,C0,M0,S2,B3,NX".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn rejects_multibyte_input_without_panicking() {
+        for s in ["é3", "é", "ř1,C0", "B3/S23é"] {
+            assert!(s.parse::<Rule>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn range_one_counting_the_centre_is_life() {
+        let larger: Rule = "R1,C0,M1,S3..4,B3,NM".parse().unwrap();
+        let (mut a, mut b) = (glider(), glider());
+        for _ in 0..12 {
+            a.step(&Rule::life());
+            b.step(&larger);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn parses_life_like_rulestrings() {
+        for s in ["B3/S23", "b3/s23", "B3S23", "S23/B3", "23/3", " B3/S23 "] {
+            assert_eq!(s.parse::<Rule>(), Ok(Rule::life()), "{s}");
+        }
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        assert!(highlife.is_birth(6) && !highlife.is_birth(2) && highlife.is_survival(3));
+        assert_eq!(highlife.to_string(), "B36/S23");
+        assert_eq!("B/S".parse::<Rule>().unwrap().to_string(), "B/S");
+    }
+
+    #[test]
+    fn rejects_bad_rulestrings() {
+        assert_eq!("".parse::<Rule>(), Err(RuleParseError::Empty));
+        assert_eq!("B9/S23".parse::<Rule>(), Err(RuleParseError::InvalidCount('9')));
+        assert_eq!("B33/S23".parse::<Rule>(), Err(RuleParseError::DuplicateCount('3')));
+        assert_eq!("B3/S23/B4".parse::<Rule>(), Err(RuleParseError::DuplicateSection('B')));
+        assert!(matches!("B3".parse::<Rule>(), Err(RuleParseError::UnknownFormat(_))));
+        assert!(matches!("Life".parse::<Rule>(), Err(RuleParseError::UnknownFormat(_))));
+    }
+
+    #[test]
+    fn other_rules_change_what_the_board_does() {
+        // under Seeds (B2/S) every live cell dies and a domino's sides light up
+        let mut grid = board(&["....", ".oo.", "...."]);
+        grid.step(&"B2/S".parse::<Rule>().unwrap());
+        assert_eq!(grid, board(&[".oo.", "....", ".oo."]));
+    }
+
+    #[test]
+    fn parses_generations_rules() {
+        let brain = Rule::generations(&[2], &[], 3);
+        for s in ["B2/S/C3", "/2/3", "B2/S/G3", "b2/s/c3"] {
+            assert_eq!(s.parse::<Rule>(), Ok(brain.clone()), "{s}");
+        }
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+        assert_eq!(star_wars.states(), 4);
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+        assert_eq!("B2/S/C1".parse::<Rule>(), Err(RuleParseError::InvalidStates("1".into())));
+        assert_eq!("B2/S/C3/C4".parse::<Rule>(), Err(RuleParseError::DuplicateSection('C')));
+    }
+
+    #[test]
+    fn cells_that_fail_to_survive_die_slowly() {
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+        assert_eq!(star_wars.next_state(ALIVE, 3), ALIVE);
+        assert_eq!(star_wars.next_state(ALIVE, 1), 2);
+        assert_eq!(star_wars.next_state(2, 3), 3);
+        assert_eq!(star_wars.next_state(3, 3), EMPTY);
+        assert_eq!(star_wars.next_state(EMPTY, 2), ALIVE);
+        assert_eq!(star_wars.next_state(2, 2), 3);
+    }
+
+    #[test]
+    fn dying_cells_neither_count_nor_come_back() {
+        // Brian's Brain: a domino fires its neighbours and then rests
+        let brain: Rule = "B2/S/C3".parse().unwrap();
+        let mut grid = board(&["....", ".oo.", "...."]);
+        grid.step(&brain);
+        assert_eq!(grid, {
+            let mut expected = board(&[".oo.", "....", ".oo."]);
+            expected.set(1, 1, 2);
+            expected.set(2, 1, 2);
+            expected
+        });
+        grid.step(&brain);
+        assert_eq!(grid.row(2), [0, 2, 2, 0]);
+        assert_eq!(grid.row(1), [1, 0, 0, 1]);
+        assert_eq!(grid.row(0), [0, 2, 2, 0]);
+    }
+
+    #[test]
+    fn custom_masks_are_read_from_the_top_left() {
+        let rule: Rule = "R1,C0,M0,S,B1,N@400".parse().unwrap();
+        assert_eq!(rule.neighbourhood(), &Neighbourhood::Custom(vec![(0, 1)]));
+        assert_eq!(rule.to_string(), "R1,C0,M0,S,B1,N@400");
+        // a lone cell under a north-only B1 rule grows downwards
+        let mut grid = board(&["...", ".o.", "..."]);
+        grid.step(&rule);
+        assert_eq!(grid, board(&["...", "...", ".o."]));
+
+        let corners: Rule = "R1,C0,M0,S2,B2,N@A28".parse().unwrap();
+        assert_eq!(corners.neighbourhood(), &Neighbourhood::Custom(vec![(-1, -1), (1, -1), (-1, 1), (1, 1)]));
+    }
+
+    #[test]
+    fn neighbourhood_suffixes_pick_the_shape() {
+        let hex: Rule = "B2/S34H".parse().unwrap();
+        assert_eq!(hex.neighbourhood(), &Neighbourhood::Hexagonal(1));
+        assert_eq!(hex.to_string(), "B2/S34H");
+        let von_neumann: Rule = "B1/S1V".parse().unwrap();
+        assert_eq!(von_neumann.neighbourhood(), &Neighbourhood::VonNeumann(1));
+        assert_eq!(
+            "B5/S23V".parse::<Rule>(),
+            Err(RuleParseError::CountOutOfRange { count: 5, max: 4 })
+        );
+    }
+
+    #[test]
+    fn parses_hensel_letters() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert!(rule.is_isotropic());
+        assert_eq!(rule.to_string(), "B2-a/S12");
+        assert_eq!("b3-ckq4z/s2-i34q".parse::<Rule>().unwrap().to_string(), "B3-ckq4z/S2-i34q");
+        assert_eq!("B2ak/S".parse::<Rule>().unwrap().to_string(), "B2ka/S");
+        assert_eq!("B2z/S".parse::<Rule>(), Err(RuleParseError::InvalidLetter { count: 2, letter: 'z' }));
+        assert_eq!("B2aa/S".parse::<Rule>(), Err(RuleParseError::DuplicateLetter { count: 2, letter: 'a' }));
+        assert_eq!("B2-/S".parse::<Rule>(), Err(RuleParseError::InvalidCount('-')));
+        assert_eq!("B2a/S34H".parse::<Rule>(), Err(RuleParseError::IsotropicNeighbourhood));
+    }
+
+    #[test]
+    fn every_letter_of_a_count_is_the_whole_count() {
+        let spelled: Rule = "B3cekainyqjr/S2cekain3cekainyqjr".parse().unwrap();
+        assert_eq!(spelled.to_string(), "B3/S23");
+        let (mut a, mut b) = (glider(), glider());
+        for _ in 0..12 {
+            a.step(&Rule::life());
+            b.step(&spelled);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn letters_tell_apart_shapes_of_the_same_count() {
+        // the cells beside a domino see two adjacent neighbours (2a), and none
+        // sees two at right angles (2e)
+        let mut adjacent = board(&["....", ".oo.", "...."]);
+        adjacent.step(&"B2a/S".parse::<Rule>().unwrap());
+        assert_eq!(adjacent, board(&[".oo.", "....", ".oo."]));
+        let mut right_angles = board(&["....", ".oo.", "...."]);
+        right_angles.step(&"B2e/S".parse::<Rule>().unwrap());
+        assert_eq!(right_angles.population(), 0);
+    }
+}
@@ -0,0 +1,733 @@
+//! Golly `.rule` files: multi-state automata given as a `@TABLE` of
+//! transitions or a `@TREE`, with optional `@COLORS`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::grid::{CellState, Grid, Transition};
+
+/// Which neighbours a rule file looks at, in the order its transitions list
+/// them after the centre cell. North is `y + 1`, as boards are read with `y`
+/// going up, so rules keep the handedness Golly gives them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableNeighbourhood {
+    VonNeumann,
+    Moore,
+    Hexagonal,
+    OneDimensional,
+}
+
+impl TableNeighbourhood {
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            TableNeighbourhood::VonNeumann => &[(0, 1), (1, 0), (0, -1), (-1, 0)],
+            TableNeighbourhood::Moore => &[(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)],
+            // hex boards are skewed so that (1, 1) is the up-right neighbour;
+            // Golly's N, E, SE, S, W, NW go clockwise from there
+            TableNeighbourhood::Hexagonal => &[(1, 1), (1, 0), (0, -1), (-1, -1), (-1, 0), (0, 1)],
+            TableNeighbourhood::OneDimensional => &[(-1, 0), (1, 0)],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "vonneumann" => Some(TableNeighbourhood::VonNeumann),
+            "moore" => Some(TableNeighbourhood::Moore),
+            "hexagonal" => Some(TableNeighbourhood::Hexagonal),
+            "onedimensional" => Some(TableNeighbourhood::OneDimensional),
+            _ => None,
+        }
+    }
+
+    /// The symmetry a `symmetries:` setting names. Every neighbourhood lists
+    /// its cells in cyclic order, so rotations are shifts and reflections
+    /// reverse the ring around the first cell.
+    fn symmetry(self, name: &str) -> Option<Symmetry> {
+        let n = self.offsets().len();
+        let rotate = |step: usize| (0..n).map(move |i| (i + step) % n).collect::<Vec<_>>();
+        let reflect = |perm: &Vec<usize>| perm.iter().map(|&i| (n - i) % n).collect::<Vec<_>>();
+        let rotations = |step: usize| (0..n).step_by(step).map(rotate).collect::<Vec<_>>();
+        let with_reflections = |perms: Vec<Vec<usize>>| {
+            let reflected: Vec<_> = perms.iter().map(reflect).collect();
+            perms.into_iter().chain(reflected).collect::<Vec<_>>()
+        };
+
+        use TableNeighbourhood::*;
+        let perms = match (self, name) {
+            (_, "none") => vec![rotate(0)],
+            (_, "permute") => return Some(Symmetry::Permute),
+            (VonNeumann | Moore, "rotate4") => rotations(n / 4),
+            (VonNeumann | Moore, "rotate4reflect") => with_reflections(rotations(n / 4)),
+            (VonNeumann | Moore | Hexagonal, "reflect_horizontal") | (OneDimensional, "reflect") => {
+                with_reflections(vec![rotate(0)])
+            }
+            (Moore, "rotate8") | (Hexagonal, "rotate6") => rotations(1),
+            (Moore, "rotate8reflect") | (Hexagonal, "rotate6reflect") => with_reflections(rotations(1)),
+            (Hexagonal, "rotate2") => rotations(3),
+            (Hexagonal, "rotate3") => rotations(2),
+            _ => return None,
+        };
+        Some(Symmetry::Permutations(perms))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Symmetry {
+    /// Neighbour orders to try, each listing which neighbour goes where.
+    Permutations(Vec<Vec<usize>>),
+    /// Neighbours may come in any order.
+    Permute,
+}
+
+impl Symmetry {
+    /// Every distinct rearrangement of `inputs` the symmetry allows, with the
+    /// centre cell left first.
+    fn images(&self, inputs: &[u8]) -> Vec<Vec<u8>> {
+        match self {
+            Symmetry::Permutations(perms) => {
+                let mut seen = HashSet::new();
+                perms
+                    .iter()
+                    .map(|perm| {
+                        let mut image = vec![inputs[0]];
+                        image.extend(perm.iter().map(|&p| inputs[p + 1]));
+                        image
+                    })
+                    .filter(|image| seen.insert(image.clone()))
+                    .collect()
+            }
+            Symmetry::Permute => {
+                let mut neighbours = inputs[1..].to_vec();
+                neighbours.sort_unstable();
+                let mut images = vec![];
+                loop {
+                    images.push([&inputs[..1], &neighbours].concat());
+                    if !next_permutation(&mut neighbours) {
+                        return images;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rearranges `values` into the next lexicographic order, returning `false`
+/// once they are back in ascending order.
+fn next_permutation(values: &mut [u8]) -> bool {
+    let Some(i) = values.windows(2).rposition(|w| w[0] < w[1]) else {
+        values.reverse();
+        return false;
+    };
+    let j = values.iter().rposition(|&v| v > values[i]).unwrap();
+    values.swap(i, j);
+    values[i + 1..].reverse();
+    true
+}
+
+/// One entry of a transition line.
+#[derive(Debug, Clone)]
+enum Token {
+    State(u8),
+    /// A variable, bound to the same value wherever it appears in the line.
+    Var(usize),
+    /// An inline `{a,b,c}` set; unlike a variable it is not bound.
+    Set(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+struct TransitionLine {
+    inputs: Vec<Token>,
+    output: Token,
+}
+
+impl TransitionLine {
+    fn matches(&self, inputs: &[u8], vars: &[Vec<u8>]) -> Option<u8> {
+        let mut bound: Vec<Option<u8>> = vec![None; vars.len()];
+        for (token, &value) in self.inputs.iter().zip(inputs) {
+            let ok = match token {
+                Token::State(s) => *s == value,
+                Token::Set(values) => values.contains(&value),
+                Token::Var(v) => match bound[*v] {
+                    Some(b) => b == value,
+                    None if vars[*v].contains(&value) => {
+                        bound[*v] = Some(value);
+                        true
+                    }
+                    None => false,
+                },
+            };
+            if !ok {
+                return None;
+            }
+        }
+        match &self.output {
+            Token::State(s) => Some(*s),
+            Token::Var(v) => bound[*v],
+            Token::Set(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Transitions {
+    Table {
+        vars: Vec<Vec<u8>>,
+        lines: Vec<TransitionLine>,
+        symmetry: Symmetry,
+        /// Results already worked out, indexed by the inputs read as digits
+        /// in base `states`, or [`UNKNOWN`]. Threads fill it in as they go
+        /// without taking turns. Empty when it would be too big, and then
+        /// each [`Transition::next_states`] call keeps a cache of its own.
+        known: Vec<AtomicU8>,
+    },
+    Tree {
+        /// Each node lists its children per state; at the bottom level the
+        /// children are the resulting states.
+        nodes: Vec<Vec<u32>>,
+    },
+}
+
+impl Clone for Transitions {
+    fn clone(&self) -> Self {
+        match self {
+            Transitions::Table { vars, lines, symmetry, known } => Transitions::Table {
+                vars: vars.clone(),
+                lines: lines.clone(),
+                symmetry: symmetry.clone(),
+                known: known.iter().map(|state| AtomicU8::new(state.load(Ordering::Relaxed))).collect(),
+            },
+            Transitions::Tree { nodes } => Transitions::Tree { nodes: nodes.clone() },
+        }
+    }
+}
+
+/// Marks a result in [`Transitions::Table`] not worked out yet.
+const UNKNOWN: u8 = u8::MAX;
+
+/// Most entries a table's results are kept in, one byte each.
+const MAX_KNOWN: usize = 1 << 22;
+
+/// A multi-state automaton loaded from a Golly `.rule` file. Transitions
+/// that the file does not cover leave the cell unchanged.
+#[derive(Debug, Clone)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    neighbourhood: TableNeighbourhood,
+    transitions: Transitions,
+    colours: Vec<[u8; 3]>,
+}
+
+impl RuleTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RuleTableError> {
+        let source = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| RuleTableError::new(0, format!("cannot read {}: {e}", path.as_ref().display())))?;
+        Self::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, RuleTableError> {
+        let mut name = String::new();
+        let mut section = "";
+        let mut table = vec![];
+        let mut tree = vec![];
+        let mut colours = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('@') {
+                let mut words = header.split_whitespace();
+                section = match words.next().unwrap_or("") {
+                    "RULE" => {
+                        name = words.next().unwrap_or("").to_string();
+                        "RULE"
+                    }
+                    "TABLE" => "TABLE",
+                    "TREE" => "TREE",
+                    "COLORS" => "COLORS",
+                    _ => "",
+                };
+                continue;
+            }
+            match section {
+                "TABLE" => table.push((number, line)),
+                "TREE" => tree.push((number, line)),
+                "COLORS" => colours.push((number, line)),
+                _ => {}
+            }
+        }
+
+        let mut rule = if !table.is_empty() {
+            parse_table(&table)?
+        } else if !tree.is_empty() {
+            parse_tree(&tree)?
+        } else {
+            return Err(RuleTableError::new(0, "no @TABLE or @TREE section"));
+        };
+        rule.name = name;
+        rule.colours = default_colours(rule.states);
+        parse_colours(&colours, &mut rule.colours)?;
+        Ok(rule)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighbourhood(&self) -> TableNeighbourhood {
+        self.neighbourhood
+    }
+
+    /// RGB colour of every state, from `@COLORS` where given.
+    pub fn colours(&self) -> &[[u8; 3]] {
+        &self.colours
+    }
+
+    /// Next state of a cell from its own state followed by its neighbours, in
+    /// the order of [`TableNeighbourhood::offsets`].
+    pub fn next_state(&self, inputs: &[u8]) -> CellState {
+        match &self.transitions {
+            Transitions::Table { known, .. } if !known.is_empty() => {
+                let index = inputs.iter().fold(0, |index, &s| index * self.states as usize + s as usize);
+                match known[index].load(Ordering::Relaxed) {
+                    UNKNOWN => {
+                        let state = self.resolve(inputs);
+                        known[index].store(state, Ordering::Relaxed);
+                        state
+                    }
+                    state => state,
+                }
+            }
+            Transitions::Table { .. } => self.resolve(inputs),
+            Transitions::Tree { nodes } => {
+                // trees read nw, ne, sw, se, n, w, e, s, c (or n, w, e, s, c)
+                let order: &[usize] = match self.neighbourhood {
+                    TableNeighbourhood::Moore => &[8, 2, 6, 4, 1, 7, 3, 5, 0],
+                    _ => &[1, 4, 2, 3, 0],
+                };
+                let mut node = nodes.len() - 1;
+                for (i, &input) in order.iter().enumerate() {
+                    let child = nodes[node][inputs[input] as usize];
+                    if i + 1 == order.len() {
+                        return child as CellState;
+                    }
+                    node = child as usize;
+                }
+                unreachable!()
+            }
+        }
+    }
+}
+
+impl RuleTable {
+    /// Next state of a cell under the table's transitions, trying each line
+    /// against every arrangement of the inputs the symmetry allows.
+    fn resolve(&self, inputs: &[u8]) -> CellState {
+        let Transitions::Table { vars, lines, symmetry, .. } = &self.transitions else {
+            unreachable!("only tables are resolved");
+        };
+        let images = symmetry.images(inputs);
+        lines
+            .iter()
+            .find_map(|line| images.iter().find_map(|image| line.matches(image, vars)))
+            .unwrap_or(inputs[0])
+    }
+}
+
+impl Transition for RuleTable {
+    fn next_states(&self, grid: &Grid) -> Vec<CellState> {
+        let offsets = self.neighbourhood.offsets();
+        let mut inputs = vec![0; offsets.len() + 1];
+        // for tables too big to keep every result, only while this call runs
+        let mut cache: HashMap<u128, CellState> = HashMap::new();
+        let uncached = matches!(&self.transitions, Transitions::Table { known, .. } if known.is_empty());
+        grid.iter()
+            .map(|(x, y, state)| {
+                inputs[0] = state;
+                for (input, &(dx, dy)) in inputs[1..].iter_mut().zip(offsets) {
+                    *input = grid.get(x + dx, y + dy);
+                }
+                if !uncached {
+                    return self.next_state(&inputs);
+                }
+                let key = inputs.iter().fold(0u128, |key, &s| key << 8 | s as u128);
+                *cache.entry(key).or_insert_with(|| self.resolve(&inputs))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RuleTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Golly's default: state 0 dark grey, the rest graded from red to yellow.
+pub(crate) fn default_colours(states: u8) -> Vec<[u8; 3]> {
+    let mut colours = vec![[48, 48, 48]];
+    colours.extend((1..states).map(|s| {
+        let t = if states > 2 { (s - 1) as f32 / (states - 2) as f32 } else { 0.0 };
+        [255, (255.0 * t) as u8, 0]
+    }));
+    colours
+}
+
+fn parse_table(lines: &[(usize, &str)]) -> Result<RuleTable, RuleTableError> {
+    let mut states = None;
+    let mut neighbourhood = None;
+    let mut symmetry = "none".to_string();
+    let mut var_names: HashMap<String, usize> = HashMap::new();
+    let mut vars: Vec<Vec<u8>> = vec![];
+    let mut transitions = vec![];
+
+    for &(number, line) in lines {
+        let err = |message: String| RuleTableError::new(number, message);
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" | "num_states" => {
+                    states = Some(value.parse::<u8>().ok().filter(|&n| n >= 2).ok_or_else(|| err(format!("'{value}' is not a state count")))?);
+                }
+                "neighborhood" => {
+                    neighbourhood = Some(TableNeighbourhood::from_name(value).ok_or_else(|| err(format!("unknown neighborhood '{value}'")))?);
+                }
+                "symmetries" => symmetry = value.to_string(),
+                other => return Err(err(format!("unknown setting '{other}'"))),
+            }
+            continue;
+        }
+
+        let (states, neighbourhood) = match (states, neighbourhood) {
+            (Some(s), Some(n)) => (s, n),
+            _ => return Err(err("n_states and neighborhood must come before variables and transitions".into())),
+        };
+
+        if let Some(declaration) = line.strip_prefix("var ") {
+            let (var, values) = declaration
+                .split_once('=')
+                .ok_or_else(|| err(format!("malformed variable '{declaration}'")))?;
+            let values = values.trim();
+            let values = values
+                .strip_prefix('{')
+                .and_then(|v| v.strip_suffix('}'))
+                .ok_or_else(|| err(format!("variable values must be in braces, found '{values}'")))?;
+            let mut set = vec![];
+            for value in values.split(',').map(str::trim) {
+                match parse_value(value, states, &var_names, &vars) {
+                    Some(Token::State(s)) => set.push(s),
+                    Some(Token::Var(v)) => set.extend(vars[v].iter().copied()),
+                    _ => return Err(err(format!("'{value}' is not a state or known variable"))),
+                }
+            }
+            var_names.insert(var.trim().to_string(), vars.len());
+            vars.push(set);
+            continue;
+        }
+
+        let tokens = split_transition(line, states, neighbourhood.offsets().len() + 2);
+        if tokens.len() != neighbourhood.offsets().len() + 2 {
+            return Err(err(format!("expected {} entries, found {}", neighbourhood.offsets().len() + 2, tokens.len())));
+        }
+        let mut tokens = tokens
+            .iter()
+            .map(|t| parse_value(t, states, &var_names, &vars).ok_or_else(|| err(format!("'{t}' is not a state, set or known variable"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let output = tokens.pop().unwrap();
+        match &output {
+            Token::Var(v) if !tokens.iter().any(|t| matches!(t, Token::Var(i) if i == v)) => {
+                return Err(err("output variable must also appear in the inputs".into()));
+            }
+            Token::Set(_) => return Err(err("output cannot be a set".into())),
+            _ => {}
+        }
+        transitions.push(TransitionLine { inputs: tokens, output });
+    }
+
+    let (states, neighbourhood) = match (states, neighbourhood) {
+        (Some(s), Some(n)) => (s, n),
+        _ => return Err(RuleTableError::new(0, "@TABLE needs n_states and neighborhood")),
+    };
+    let symmetry = neighbourhood.symmetry(&symmetry).ok_or_else(|| {
+        RuleTableError::new(0, format!("symmetries '{symmetry}' do not apply to this neighborhood"))
+    })?;
+    // states stop at 254, so no result is ever mistaken for UNKNOWN
+    let entries = (states as usize).checked_pow(neighbourhood.offsets().len() as u32 + 1).filter(|&n| n <= MAX_KNOWN);
+    Ok(RuleTable {
+        name: String::new(),
+        states,
+        neighbourhood,
+        transitions: Transitions::Table {
+            vars,
+            lines: transitions,
+            symmetry,
+            known: (0..entries.unwrap_or(0)).map(|_| AtomicU8::new(UNKNOWN)).collect(),
+        },
+        colours: vec![],
+    })
+}
+
+/// Splits a transition on commas and spaces, keeping `{...}` sets whole. A
+/// line of bare digits with nothing between them is the compact form.
+fn split_transition(line: &str, states: u8, entries: usize) -> Vec<String> {
+    if states <= 10 && line.len() == entries && line.chars().all(|c| c.is_ascii_digit()) {
+        return line.chars().map(String::from).collect();
+    }
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in line.chars() {
+        match c {
+            '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' | ' ' | '\t' if depth == 0 => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_value(value: &str, states: u8, var_names: &HashMap<String, usize>, vars: &[Vec<u8>]) -> Option<Token> {
+    if let Ok(state) = value.parse::<u8>() {
+        return (state < states).then_some(Token::State(state));
+    }
+    if let Some(inner) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+        let mut set = vec![];
+        for v in inner.split(',').map(str::trim) {
+            match parse_value(v, states, var_names, vars)? {
+                Token::State(s) => set.push(s),
+                Token::Var(i) => set.extend(vars[i].iter().copied()),
+                Token::Set(s) => set.extend(s),
+            }
+        }
+        return Some(Token::Set(set));
+    }
+    var_names.get(value).map(|&v| Token::Var(v))
+}
+
+fn parse_tree(lines: &[(usize, &str)]) -> Result<RuleTable, RuleTableError> {
+    let mut states = None;
+    let mut neighbours = None;
+    let mut nodes: Vec<Vec<u32>> = vec![];
+    let mut levels: Vec<u32> = vec![];
+
+    for &(number, line) in lines {
+        let err = |message: String| RuleTableError::new(number, message);
+        if let Some((key, value)) = line.split_once('=') {
+            let value: usize = value.trim().parse().map_err(|_| err(format!("'{}' is not a number", value.trim())))?;
+            match key.trim() {
+                "num_states" => states = Some(u8::try_from(value).ok().filter(|&n| n >= 2).ok_or_else(|| err(format!("{value} is not a state count")))?),
+                "num_neighbors" if value == 4 || value == 8 => neighbours = Some(value),
+                "num_neighbors" => return Err(err("trees need 4 or 8 neighbors".into())),
+                "num_nodes" => nodes.reserve(value),
+                other => return Err(err(format!("unknown setting '{other}'"))),
+            }
+            continue;
+        }
+
+        let states = states.ok_or_else(|| err("num_states must come before the nodes".into()))?;
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<u32>().map_err(|_| err(format!("'{n}' is not a number"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (&level, children) = numbers.split_first().ok_or_else(|| err("empty node".into()))?;
+        if level == 0 {
+            return Err(err("nodes start at level 1".into()));
+        }
+        if children.len() != states as usize {
+            return Err(err(format!("node needs {states} children, found {}", children.len())));
+        }
+        for &child in children {
+            let valid = if level == 1 {
+                child < states as u32
+            } else {
+                levels.get(child as usize) == Some(&(level - 1))
+            };
+            if !valid {
+                return Err(err(format!("child {child} does not fit a level {level} node")));
+            }
+        }
+        levels.push(level);
+        nodes.push(children.to_vec());
+    }
+
+    let (states, neighbours) = match (states, neighbours) {
+        (Some(s), Some(n)) => (s, n),
+        _ => return Err(RuleTableError::new(0, "@TREE needs num_states and num_neighbors")),
+    };
+    if levels.last() != Some(&(neighbours as u32 + 1)) {
+        return Err(RuleTableError::new(0, "the last node of a tree must be its root"));
+    }
+    Ok(RuleTable {
+        name: String::new(),
+        states,
+        neighbourhood: if neighbours == 8 { TableNeighbourhood::Moore } else { TableNeighbourhood::VonNeumann },
+        transitions: Transitions::Tree { nodes },
+        colours: vec![],
+    })
+}
+
+/// Reads `state r g b` lines, or `r g b r g b` for a gradient over every
+/// state but 0.
+fn parse_colours(lines: &[(usize, &str)], colours: &mut [[u8; 3]]) -> Result<(), RuleTableError> {
+    for &(number, line) in lines {
+        let err = || RuleTableError::new(number, format!("'{line}' is not a colour"));
+        let numbers = line
+            .split_whitespace()
+            .map(|n| n.parse::<u8>().map_err(|_| err()))
+            .collect::<Result<Vec<_>, _>>()?;
+        match numbers[..] {
+            [state, r, g, b] => {
+                if let Some(colour) = colours.get_mut(state as usize) {
+                    *colour = [r, g, b];
+                }
+            }
+            [r0, g0, b0, r1, g1, b1] => {
+                let last = colours.len().saturating_sub(2).max(1) as f32;
+                for (i, colour) in colours.iter_mut().enumerate().skip(1) {
+                    let t = (i - 1) as f32 / last;
+                    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                    *colour = [mix(r0, r1), mix(g0, g1), mix(b0, b1)];
+                }
+            }
+            _ => return Err(err()),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTableError {
+    /// 1-based line in the rule file, or 0 for the file as a whole.
+    pub line: usize,
+    pub message: String,
+}
+
+impl RuleTableError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RuleTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.message),
+            line => write!(f, "line {line}: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuleTableError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{board, keyed_board};
+
+    fn wireworld() -> RuleTable {
+        RuleTable::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/rules/WireWorld.rule")).unwrap()
+    }
+
+    /// A rule where every cell takes the state of its neighbour `which`, in
+    /// Golly's order.
+    fn follow(neighbourhood: &str, neighbours: usize, which: usize) -> RuleTable {
+        let vars: Vec<_> = (0..=neighbours).map(|i| format!("v{i}")).collect();
+        let mut source = format!("@TABLE\nn_states:2\nneighborhood:{neighbourhood}\nsymmetries:none\n");
+        for var in &vars {
+            source += &format!("var {var}={{0,1}}\n");
+        }
+        source += &format!("{},{}\n", vars.join(","), vars[which]);
+        RuleTable::parse(&source).unwrap()
+    }
+
+    #[test]
+    fn north_is_up() {
+        let cases = [
+            ("vonNeumann", 4, 1, ["...", "...", ".o."]),
+            ("Moore", 8, 1, ["...", "...", ".o."]),
+            ("Moore", 8, 2, ["...", "...", "o.."]),
+            ("Moore", 8, 4, ["o..", "...", "..."]),
+            ("Moore", 8, 8, ["...", "...", "..o"]),
+            ("hexagonal", 6, 1, ["...", "...", "o.."]),
+            ("hexagonal", 6, 3, [".o.", "...", "..."]),
+        ];
+        for (neighbourhood, neighbours, which, expected) in cases {
+            let rule = follow(neighbourhood, neighbours, which);
+            let mut grid = board(&["...", ".o.", "..."]);
+            grid.step(&rule);
+            assert_eq!(grid, board(&expected), "{neighbourhood} neighbour {which}");
+        }
+    }
+
+    #[test]
+    fn electrons_run_along_wires() {
+        let wireworld = wireworld();
+        let mut grid = keyed_board(&["TH########"], "HT#");
+        for _ in 0..5 {
+            grid.step(&wireworld);
+        }
+        assert_eq!(grid, keyed_board(&["#####TH###"], "HT#"));
+    }
+
+    #[test]
+    fn diode_passes_electrons_one_way() {
+        let wireworld = wireworld();
+        let diode = |wire| ["......##........", wire, "......##........"];
+        let mut forward = keyed_board(&diode("TH#####.#####..."), "HT#");
+        let mut backward = keyed_board(&diode("...#####.#####HT"), "HT#");
+        for _ in 0..10 {
+            forward.step(&wireworld);
+            backward.step(&wireworld);
+        }
+        assert_eq!(forward, keyed_board(&diode("#######.##TH#..."), "HT#"));
+        assert_eq!(backward, keyed_board(&diode("...#####.#######"), "HT#"));
+    }
+
+    #[test]
+    fn tables_too_big_to_keep_agree_with_kept_ones() {
+        let kept = wireworld();
+        let mut big = kept.clone();
+        if let Transitions::Table { known, .. } = &mut big.transitions {
+            known.clear();
+        }
+        let mut grid = keyed_board(&["......##........", "TH#####.#####HT.", "......##........"], "HT#");
+        for _ in 0..12 {
+            let next = kept.next_states(&grid);
+            assert_eq!(big.next_states(&grid), next);
+            grid.step(&kept);
+        }
+    }
+
+    #[test]
+    fn malformed_trees_are_refused() {
+        let tree = |nodes: &str| RuleTable::parse(&format!("@RULE Bad\n@TREE\nnum_states=2\nnum_neighbors=4\nnum_nodes=1\n{nodes}\n"));
+        assert!(tree("0 0 0").is_err());
+        assert!(tree("1 0 2").is_err());
+        assert!(tree("1 0 1\n3 0 0").is_err());
+        assert!(tree("1 0 1").is_err());
+    }
+}
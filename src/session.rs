@@ -0,0 +1,301 @@
+//! Saved sessions: the board as an RLE pattern, plus the rule, seed and
+//! generation needed to carry on a run exactly where it left off.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use crate::grid::{CellState, Grid, EMPTY};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Rulestring, rule file name or anything else the app reads as a rule.
+    pub rule: String,
+    pub seed: u64,
+    pub generation: u64,
+    pub grid: Grid,
+    /// Where the grid's bottom-left cell sits on an unbounded board.
+    pub origin: (i64, i64),
+}
+
+impl Session {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        std::fs::write(path.as_ref(), self.to_string())
+            .map_err(|e| SessionError::Io(format!("cannot write {}: {e}", path.as_ref().display())))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        std::fs::read_to_string(path.as_ref())
+            .map_err(|e| SessionError::Io(format!("cannot read {}: {e}", path.as_ref().display())))?
+            .parse()
+    }
+}
+
+/// RLE symbol for a cell. Two-state boards use Life's `b` and `o`, others
+/// `.` for empty and `A`, `B`, ... with a `p`-`y` prefix past `X`, as Golly does.
+fn symbol(state: CellState, two_state: bool) -> String {
+    match (state, two_state) {
+        (EMPTY, true) => "b".into(),
+        (_, true) => "o".into(),
+        (EMPTY, false) => ".".into(),
+        (s, false) if s <= 24 => ((b'A' + s - 1) as char).to_string(),
+        (s, false) => format!("{}{}", (b'p' + (s - 25) / 24) as char, (b'A' + (s - 25) % 24) as char),
+    }
+}
+
+/// Written as an extended RLE file that Golly can open. Rows run from the
+/// top of the board down. A non-zero origin is kept in Golly's `#CXRLE Pos`
+/// line, which gives the top-left cell with y growing downwards.
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grid = &self.grid;
+        if self.origin != (0, 0) {
+            let (x, y) = self.origin;
+            writeln!(f, "#CXRLE Pos={},{}", x, -(y + grid.height() as i64 - 1))?;
+        }
+        writeln!(f, "#C seed {}", self.seed)?;
+        writeln!(f, "#C generation {}", self.generation)?;
+        writeln!(f, "x = {}, y = {}, rule = {}", grid.width(), grid.height(), self.rule)?;
+
+        let two_state = grid.iter().all(|(_, _, state)| state <= 1);
+        let mut runs: Vec<(u32, String)> = vec![];
+        let push = |runs: &mut Vec<(u32, String)>, count: u32, symbol: String| match runs.last_mut() {
+            Some((n, last)) if *last == symbol => *n += count,
+            _ => runs.push((count, symbol)),
+        };
+        for y in (0..grid.height()).rev() {
+            let width = (0..grid.width()).rev().find(|&x| grid.get(x, y) != EMPTY).map_or(0, |x| x + 1);
+            for x in 0..width {
+                push(&mut runs, 1, symbol(grid.get(x, y), two_state));
+            }
+            push(&mut runs, 1, "$".into());
+        }
+        while matches!(runs.last(), Some((_, s)) if s == "$") {
+            runs.pop();
+        }
+        push(&mut runs, 1, "!".into());
+
+        let mut line = String::new();
+        for (count, symbol) in runs {
+            let item = if count > 1 { format!("{count}{symbol}") } else { symbol };
+            if line.len() + item.len() > 70 {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            line.push_str(&item);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+impl FromStr for Session {
+    type Err = SessionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut seed = 0;
+        let mut generation = 0;
+        let mut position = None;
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        let header = loop {
+            let line = lines.next().ok_or(SessionError::MissingHeader)?;
+            let Some(comment) = line.strip_prefix('#') else {
+                break line;
+            };
+            if let Some(pos) = comment.strip_prefix("CXRLE").and_then(|c| c.split_once("Pos=")) {
+                let mut coords = pos.1.split_whitespace().next().unwrap_or("").split(',');
+                let mut coord = || coords.next().and_then(|n| n.trim().parse::<i64>().ok());
+                position = coord().zip(coord());
+                continue;
+            }
+            let mut words = comment.split_whitespace().skip(1);
+            match (words.next(), words.next().and_then(|n| n.parse().ok())) {
+                (Some("seed"), Some(n)) => seed = n,
+                (Some("generation"), Some(n)) => generation = n,
+                _ => {}
+            }
+        };
+
+        // the rule runs to the end of the line, since rulestrings may hold commas
+        let (size, rule) = match header.find("rule") {
+            Some(i) => (&header[..i], header[i + 4..].trim_start().trim_start_matches('=').trim()),
+            None => (header, ""),
+        };
+        let mut width = None;
+        let mut height = None;
+        for field in size.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| SessionError::InvalidHeader(header.to_string()))?;
+            let value = value.trim().parse::<i32>().ok().filter(|&n| n > 0);
+            match key.trim() {
+                "x" => width = value,
+                "y" => height = value,
+                _ => {}
+            }
+        }
+        let (width, height) = width.zip(height).ok_or_else(|| SessionError::InvalidHeader(header.to_string()))?;
+
+        let mut grid = Grid::new(width, height);
+        let (mut x, mut y) = (0, height - 1);
+        let mut count: Option<i32> = None;
+        let mut prefix = None;
+        'body: for c in lines.flat_map(str::chars) {
+            let state = match c {
+                '0'..='9' => {
+                    // a count too big for any board is out of bounds anyway
+                    let digit = c as i32 - '0' as i32;
+                    count = Some(
+                        count
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|n| n.checked_add(digit))
+                            .ok_or(SessionError::OutOfBounds)?,
+                    );
+                    continue;
+                }
+                'p'..='y' => {
+                    prefix = Some(c as u8 - b'p');
+                    continue;
+                }
+                '!' => break 'body,
+                '$' => {
+                    // one row past the bottom is where a trailing `$` leaves off
+                    y -= count.take().unwrap_or(1);
+                    if y < -1 {
+                        return Err(SessionError::OutOfBounds);
+                    }
+                    x = 0;
+                    continue;
+                }
+                'b' | '.' => EMPTY,
+                'o' => 1,
+                'A'..='X' => match prefix.take() {
+                    Some(p) => 25 + p * 24 + (c as u8 - b'A'),
+                    None => 1 + (c as u8 - b'A'),
+                },
+                c if c.is_whitespace() => continue,
+                c => return Err(SessionError::InvalidCell(c)),
+            };
+            let run = count.take().unwrap_or(1);
+            if run > width - x || (state != EMPTY && y < 0) {
+                return Err(SessionError::OutOfBounds);
+            }
+            if state != EMPTY {
+                for x in x..x + run {
+                    grid.set(x, y, state);
+                }
+            }
+            x += run;
+        }
+
+        let origin = position.map_or((0, 0), |(x, y)| (x, -y - (height as i64 - 1)));
+        Ok(Session {
+            rule: rule.to_string(),
+            seed,
+            generation,
+            grid,
+            origin,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    Io(String),
+    MissingHeader,
+    InvalidHeader(String),
+    InvalidCell(char),
+    OutOfBounds,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "{e}"),
+            SessionError::MissingHeader => write!(f, "no 'x = ..., y = ...' header line"),
+            SessionError::InvalidHeader(s) => write!(f, "'{s}' is not a valid header line"),
+            SessionError::InvalidCell(c) => write!(f, "'{c}' is not a cell state"),
+            SessionError::OutOfBounds => write!(f, "pattern runs outside its x by y size"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::ALIVE;
+
+    fn session(grid: Grid, origin: (i64, i64)) -> Session {
+        Session {
+            rule: "B3/S23:T20,10~b0.5,seed3".into(),
+            seed: 42,
+            generation: 1234,
+            grid,
+            origin,
+        }
+    }
+
+    #[test]
+    fn reads_a_plain_glider() {
+        let loaded: Session = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!".parse().unwrap();
+        assert_eq!(loaded.rule, "B3/S23");
+        assert_eq!((loaded.seed, loaded.generation, loaded.origin), (0, 0, (0, 0)));
+        let mut glider = Grid::new(3, 3);
+        for (x, y) in [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)] {
+            glider.set(x, y, ALIVE);
+        }
+        assert_eq!(loaded.grid, glider);
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut grid = Grid::new(80, 7);
+        for x in (0..80).step_by(3) {
+            grid.set(x, x % 7, ALIVE);
+        }
+        grid.set(79, 6, ALIVE);
+        let saved = session(grid, (-40, 12));
+        assert!(saved.to_string().lines().all(|line| line.len() <= 70));
+        assert_eq!(saved.to_string().parse(), Ok(saved));
+    }
+
+    #[test]
+    fn round_trips_many_states() {
+        let mut grid = Grid::new(6, 2);
+        for (x, state) in [2, 24, 25, 48, 49, 255].into_iter().enumerate() {
+            grid.set(x as i32, 1, state);
+        }
+        grid.set(0, 0, ALIVE);
+        let saved = session(grid, (0, 0));
+        assert!(saved.to_string().contains("BXpApXqAyO"));
+        assert_eq!(saved.to_string().parse(), Ok(saved));
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("gol-session-{}.rle", std::process::id()));
+        let mut grid = Grid::new(4, 4);
+        grid.set(3, 3, 2);
+        let saved = session(grid, (7, -7));
+        saved.save(&path).unwrap();
+        let loaded = Session::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Ok(saved));
+        assert!(matches!(Session::load(&path), Err(SessionError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert_eq!("#C seed 1".parse::<Session>(), Err(SessionError::MissingHeader));
+        assert!(matches!("x = 3, rule = B3/S23\no!".parse::<Session>(), Err(SessionError::InvalidHeader(_))));
+        assert_eq!("x = 2, y = 1\nooo!".parse::<Session>(), Err(SessionError::OutOfBounds));
+        assert_eq!("x = 2, y = 1\noz!".parse::<Session>(), Err(SessionError::InvalidCell('z')));
+        // runs and line breaks past the board, however long, are refused at once
+        assert_eq!("x = 1, y = 1\n2147483647b!".parse::<Session>(), Err(SessionError::OutOfBounds));
+        assert_eq!("x = 1, y = 1\n99999999999999999999o!".parse::<Session>(), Err(SessionError::OutOfBounds));
+        assert_eq!("x = 1, y = 1\n2147483647$2147483647$!".parse::<Session>(), Err(SessionError::OutOfBounds));
+        assert_eq!("x = 2, y = 2\n2$o!".parse::<Session>(), Err(SessionError::OutOfBounds));
+        assert_eq!("x = 3, y = 1\n3b$!".parse::<Session>().map(|s| s.grid.population()), Ok(0));
+    }
+}
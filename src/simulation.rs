@@ -1,33 +1,540 @@
 use bevy::{prelude::*, time::FixedTimestep, app::AppExit};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
 use bevy::ecs::archetype::Archetype;
+use bevy::tasks::ComputeTaskPool;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use game_of_life::automaton::Automaton;
+use game_of_life::census::{Census, Library};
+use game_of_life::fate::{CycleDetector, Fate, MAX_PERIOD};
+use game_of_life::grid::{CellState, Grid, Transition, ALIVE, EMPTY};
+use game_of_life::hashlife::{HashLife, MAX_STEP};
+use game_of_life::history::{Change, History};
+use game_of_life::lenia::Field;
+use game_of_life::noise::{Noise, Rng};
+use game_of_life::rule::Rule;
+use game_of_life::ruletable::RuleTable;
+use game_of_life::session::Session;
+use game_of_life::timeline::{Snapshot, Timeline};
+use game_of_life::topology::Topology;
+use game_of_life::universe::{chunk_of, Universe};
 use crate::input::MainCamera;
-use crate::ui::{GameExitEvent, ResetSimulationEvent, StartSimulationEvent, StopSimulationEvent};
+use crate::ui::{
+    ChangeRuleEvent, ClearBoardEvent, CycleTopologyEvent, ExportCensusEvent, GameExitEvent, LoadSessionEvent, ResetSimulationEvent, RuleInput, SaveSessionEvent,
+    RewindEvent, RunToEvent, StartSimulationEvent, StepBackEvent, StepOnceEvent, StopSimulationEvent, TakeCensusEvent,
+    ToggleHashLifeEvent,
+};
 
 const CELL_SIZE: f32 = 32.0;
-const GRID_SIZE: i32 = 100;
+/// Side of a bounded board unless another size is asked for.
+const DEFAULT_BOARD_SIZE: i32 = 100;
+/// Largest side a bounded board may have.
+const MAX_BOARD_SIZE: i32 = 4096;
+/// Fewest rows of a bounded board stepped together as one task, so the
+/// margin each band reads stays small next to its own rows.
+const MIN_BAND_HEIGHT: i32 = 8;
+/// Longest the simulation may run in a frame.
+const FRAME_BUDGET: Duration = Duration::from_millis(12);
+/// Rows of the board image each task of [`draw_board`] fills in.
+const IMAGE_BAND: usize = 64;
+/// Largest side of the board image, in texels. Software GL only promises
+/// textures this big.
+const MAX_IMAGE_SIZE: i64 = 2048;
+/// Colour of an empty cell.
+const EMPTY_COLOUR: [u8; 4] = [26, 25, 32, 255];
+/// Past generations kept for rewinding.
+const TIMELINE_LENGTH: usize = 512;
+const RULES_DIR: &str = "assets/rules";
+const SESSION_FILE: &str = "session.rle";
+const CENSUS_FILE: &str = "census.csv";
 
 pub struct CellMarkedForDeathEvent(Entity);
 
+#[derive(Resource)]
+pub struct Board {
+    /// Cells of discrete rules. Bounded topologies keep to the [`BoardSize`]
+    /// rectangle from the origin.
+    universe: Universe,
+    /// While HashLife runs, the cells live in its tree instead of `universe`.
+    hashlife: Option<HashLife<Automaton>>,
+    /// Cell values for continuous rules, which leave `grid` alone.
+    field: Field,
+    generation: u64,
+    /// Chunks whose cells changed since the sprites were last drawn.
+    redraw: HashSet<(i64, i64)>,
+    /// Edits made by hand, for undo and redo.
+    history: History<CellValue>,
+    /// Generations the plain stepper went through, for rewinding.
+    timeline: Timeline<Frame>,
+    /// The board and its generation when Start was last pressed, for Reset.
+    start: Option<(u64, Frame)>,
+    /// Watches the plain stepper for the board dying out or repeating.
+    detector: CycleDetector,
+}
+
+/// The board as it was at some past generation.
+pub struct Frame {
+    cells: Snapshot,
+    /// Kept only while a continuous rule runs.
+    field: Option<Field>,
+}
+
+/// What an edit changed a cell from or to: a state, or a field value for
+/// continuous rules.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CellValue {
+    State(CellState),
+    Level(f32),
+}
+
+impl Board {
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn get(&self, x: i64, y: i64) -> CellState {
+        match &self.hashlife {
+            Some(hashlife) => hashlife.get(x, y),
+            None => self.universe.get(x, y),
+        }
+    }
+
+    /// How the run settled, once it has.
+    pub fn fate(&self) -> Option<Fate> {
+        self.detector.fate()
+    }
+
+    /// Hands the board as it is now to the detector, returning the run's fate
+    /// if this is where it is found.
+    fn observe(&mut self) -> Option<Fate> {
+        self.detector.observe(self.generation, self.universe.zobrist(), self.universe.is_empty())
+    }
+
+    pub fn timeline(&self) -> &Timeline<Frame> {
+        &self.timeline
+    }
+
+    /// The board as it is now. Call [`Board::settle`] first.
+    fn frame(&self, continuous: bool) -> Frame {
+        Frame {
+            cells: Snapshot::of(&self.universe),
+            field: continuous.then(|| self.field.clone()),
+        }
+    }
+
+    /// Adds the board as it is now to the timeline.
+    fn record(&mut self, continuous: bool) {
+        let frame = self.frame(continuous);
+        self.timeline.record(self.generation, frame);
+    }
+
+    /// Puts back frame `i` of the timeline. Unless the board is already
+    /// rewound, it is recorded first so it can be come back to.
+    fn rewind(&mut self, i: usize, continuous: bool) {
+        if self.timeline.position().is_none() {
+            self.settle();
+            self.record(continuous);
+        }
+        let Some((generation, frame)) = self.timeline.seek(i) else {
+            return;
+        };
+        let cells = frame.cells.to_universe();
+        let field = frame.field.clone();
+        // redraw where the cells were as well as where they will be
+        self.detector.forget();
+        self.hashlife = None;
+        self.universe.touch_all();
+        self.redraw.extend(self.universe.changed_chunks());
+        self.universe = cells;
+        self.redraw.extend(self.universe.changed_chunks());
+        if let Some(field) = field {
+            self.field = field;
+        }
+        self.generation = generation;
+    }
+
+    /// Rewinds to the frame before the current one, if there is one.
+    fn step_back(&mut self, continuous: bool) {
+        if self.timeline.position().is_none() {
+            self.settle();
+            self.record(continuous);
+        }
+        let current = self.timeline.position().unwrap_or(self.timeline.len() - 1);
+        if let Some(i) = current.checked_sub(1) {
+            self.rewind(i, continuous);
+        }
+    }
+
+    /// Sets a cell as part of the open edit.
+    fn set(&mut self, x: i64, y: i64, state: CellState) {
+        let before = self.get(x, y);
+        self.history.record(x, y, CellValue::State(before), CellValue::State(state));
+        self.put(x, y, state);
+    }
+
+    /// Sets a field value as part of the open edit.
+    fn paint(&mut self, x: i32, y: i32, value: f32) {
+        let before = self.field.get(x, y);
+        if self.field.set(x, y, value) {
+            self.detector.forget();
+            let after = self.field.get(x, y);
+            self.history.record(x as i64, y as i64, CellValue::Level(before), CellValue::Level(after));
+        }
+    }
+
+    /// Replaces the cells with those of `universe` and the field values with
+    /// those of `field`, as an edit of its own.
+    fn replace(&mut self, universe: Universe, field: &Field) {
+        self.settle();
+        self.history.commit();
+        let gone: Vec<_> = self.universe.iter().filter(|&(x, y, _)| universe.get(x, y) == EMPTY).collect();
+        for (x, y, _) in gone {
+            self.set(x, y, EMPTY);
+        }
+        for (x, y, state) in universe.iter() {
+            self.set(x, y, state);
+        }
+        for y in 0..self.field.height() {
+            for x in 0..self.field.width() {
+                self.paint(x, y, field.get(x, y));
+            }
+        }
+        self.history.commit();
+    }
+
+    /// Puts back the board from when Start was last pressed, generation and
+    /// all, as an edit of its own.
+    fn reset(&mut self) {
+        let Some((generation, frame)) = &self.start else {
+            return;
+        };
+        let (generation, universe) = (*generation, frame.cells.to_universe());
+        let field = frame.field.clone().unwrap_or_else(|| self.field.clone());
+        self.replace(universe, &field);
+        self.generation = generation;
+        self.timeline.clear();
+    }
+
+    /// Empties the board and starts counting generations again, as an edit of
+    /// its own.
+    fn clear(&mut self) {
+        let field = Field::new(self.field.width(), self.field.height());
+        self.replace(Universe::new(), &field);
+        self.generation = 0;
+        self.timeline.clear();
+    }
+
+    /// Takes back the last edit, if there is one.
+    fn undo(&mut self) {
+        if let Some(changes) = self.history.undo() {
+            self.apply(&changes);
+        }
+    }
+
+    /// Makes the last undone edit again, if there is one.
+    fn redo(&mut self) {
+        if let Some(changes) = self.history.redo() {
+            self.apply(&changes);
+        }
+    }
+
+    fn apply(&mut self, changes: &[Change<CellValue>]) {
+        for change in changes {
+            match change.after {
+                CellValue::State(state) => self.put(change.x, change.y, state),
+                CellValue::Level(value) => {
+                    self.field.set(change.x as i32, change.y as i32, value);
+                }
+            }
+        }
+    }
+
+    /// Sets a cell without recording it.
+    fn put(&mut self, x: i64, y: i64, state: CellState) {
+        self.detector.forget();
+        self.redraw.insert(chunk_of(x, y));
+        match &mut self.hashlife {
+            Some(hashlife) => hashlife.set(x, y, state),
+            None => self.universe.set(x, y, state),
+        }
+    }
+
+    /// Moves the cells out of HashLife's tree and back into `universe`.
+    /// HashLife starts again from there on its next tick if it is still on.
+    fn settle(&mut self) {
+        if let Some(hashlife) = self.hashlife.take() {
+            self.universe = hashlife.to_universe();
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ActiveRule(pub Automaton);
+
+/// State the left mouse button paints, picked with the number keys.
+#[derive(Resource)]
+pub struct DrawState(pub CellState);
+
+/// Randomness laid over the active rule, and the seed all of it comes from.
+#[derive(Resource)]
+pub struct ActiveNoise {
+    pub noise: Noise,
+    pub seed: u64,
+}
+
+/// What lies beyond the edges of the board.
+#[derive(Resource)]
+pub struct ActiveTopology(pub Topology);
+
+/// Width and height of the board when it has edges, or when the rule needs
+/// them, and of the field continuous rules run on.
+#[derive(Resource, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BoardSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl BoardSize {
+    /// Whether `(x, y)` lies on the board, which starts at the origin.
+    fn contains(self, x: i64, y: i64) -> bool {
+        (0..self.width as i64).contains(&x) && (0..self.height as i64).contains(&y)
+    }
+
+    /// Where the middle of the board is in the world, near enough for hex
+    /// boards too.
+    pub fn centre(self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * CELL_SIZE / 2.0
+    }
+
+    /// The size in a Golly bounded grid suffix such as `T200,150`, or `None`
+    /// if it gives none. A single number is a square board.
+    fn from_spec(spec: &str) -> Result<Option<Self>, String> {
+        let size = spec.get(1..).unwrap_or("").replace('*', "");
+        if size.trim().is_empty() {
+            Ok(None)
+        } else {
+            size.parse().map(Some)
+        }
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_BOARD_SIZE,
+            height: DEFAULT_BOARD_SIZE,
+        }
+    }
+}
+
+/// Accepts `200`, `200x150` or `200,150`.
+impl FromStr for BoardSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.split_once(['x', 'X', ',']).unwrap_or((s, s));
+        let side = |n: &str| {
+            n.trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|n| (1..=MAX_BOARD_SIZE).contains(n))
+                .ok_or_else(|| format!("'{s}' is not a board size, expected sides from 1 to {MAX_BOARD_SIZE}"))
+        };
+        Ok(Self {
+            width: side(width)?,
+            height: side(height)?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Engine {
+    /// One generation per tick, for any rule.
+    Plain,
+    /// HashLife, jumping `2^step` generations per tick.
+    HashLife,
+}
+
+/// How fast the simulation runs, and where to.
+#[derive(Resource)]
+pub struct Speed {
+    /// Steps a second, between [`MIN_SPEED`] and [`MAX_SPEED`]. A HashLife
+    /// step jumps `2^k` generations.
+    pub steps_per_second: f32,
+    /// Generation a run started with [`RunToEvent`] pauses at.
+    pub target: Option<u64>,
+    /// Part of a step carried over to the next frame.
+    owed: f32,
+}
+
+pub const MIN_SPEED: f32 = 1.0;
+pub const MAX_SPEED: f32 = 4096.0;
+
+/// Whether the run pauses by itself once the board dies out, stops changing
+/// or starts repeating.
+#[derive(Resource)]
+pub struct AutoPause(pub bool);
+
+/// The objects on the board when the census was last taken, and that
+/// generation.
 #[derive(Resource, Default)]
-struct Board {
-    cells: Vec<Cell>,
+pub struct ObjectCensus(pub Option<(u64, Census)>);
+
+/// How discrete rules are advanced.
+#[derive(Resource)]
+pub struct Stepper {
+    pub engine: Engine,
+    /// HashLife advances `2^step` generations per tick.
+    pub step: u8,
+}
+
+impl Stepper {
+    /// Whether HashLife can run `rule`: it needs a range 1 rule on the
+    /// unbounded plane, and no noise, since its results are reused. B0 rules
+    /// are out too, as HashLife takes empty space to stay empty.
+    pub fn can_hash(rule: &Automaton, noise: &Noise, topology: Topology) -> bool {
+        rule.reach() == Some(1) && !rule.is_born_from_nothing() && !noise.is_random() && !topology.is_bounded()
+    }
+}
+
+pub struct SimulationPlugin {
+    pub rule: Automaton,
+    pub noise: Noise,
+    pub seed: u64,
+    pub topology: Topology,
+    pub size: BoardSize,
+}
+
+/// A rule as typed in or saved: the rule, optionally followed by a Golly
+/// bounded grid suffix such as `:T100,100`, then optionally `~` and its noise,
+/// where a `seed` field also sets the seed, e.g. `B3/S23:T~b0.9,m0.001,seed42`.
+/// A size in the suffix resizes the board.
+pub struct RuleLine {
+    pub rule: Automaton,
+    pub noise: Noise,
+    pub seed: Option<u64>,
+    pub topology: Option<Topology>,
+    pub size: Option<BoardSize>,
+}
+
+impl RuleLine {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (rule, noise) = text.split_once('~').unwrap_or((text, ""));
+        // Lenia rules use ':' too, so only a suffix that reads as a topology is one
+        let (rule, topology, size) = match rule.rsplit_once(':') {
+            Some((base, suffix)) => match suffix.parse::<Topology>() {
+                Ok(topology) => (base, Some(topology), BoardSize::from_spec(suffix.trim())?),
+                Err(_) => (rule, None, None),
+            },
+            None => (rule, None, None),
+        };
+        let mut seed = None;
+        let mut fields = vec![];
+        for field in noise.split(',').map(str::trim) {
+            match field.strip_prefix("seed") {
+                Some(n) => seed = Some(n.parse().map_err(|_| format!("'{n}' is not a seed"))?),
+                None => fields.push(field),
+            }
+        }
+        Ok(Self {
+            rule: load_automaton(rule.trim())?,
+            noise: fields.join(",").parse().map_err(|e| format!("{e}"))?,
+            seed,
+            topology,
+            size,
+        })
+    }
 }
 
-pub struct SimulationPlugin;
+/// The rule the way [`RuleLine::parse`] reads it back, leaving out the seed.
+/// The unbounded plane is the default and gets no suffix.
+pub fn rule_line(rule: &Automaton, noise: &Noise, topology: Topology, size: BoardSize) -> String {
+    let mut line = rule.to_string();
+    if topology.is_bounded() {
+        line = format!("{line}:{}", topology.spec(size.width, size.height));
+    }
+    if noise.is_random() {
+        line = format!("{line}~{noise}");
+    }
+    line
+}
+
+/// A seed for runs that were not given one.
+pub fn fresh_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Looks a rule up by rulestring, by path to a `.rule` file, or by the name
+/// of a rule file in `assets/rules`.
+pub fn load_automaton(name: &str) -> Result<Automaton, String> {
+    let parse_error = match name.parse::<Automaton>() {
+        Ok(rule) => return Ok(rule),
+        Err(e) => e,
+    };
+    let named = Path::new(RULES_DIR).join(format!("{name}.rule"));
+    let path = [Path::new(name), named.as_path()].into_iter().find(|p| p.is_file());
+    match path {
+        Some(path) => RuleTable::load(path).map(Automaton::from).map_err(|e| e.to_string()),
+        None => Err(parse_error.to_string()),
+    }
+}
 
 impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<CellMarkedForDeathEvent>()
+            .insert_resource(ActiveRule(self.rule.clone()))
+            .insert_resource(DrawState(ALIVE))
+            .insert_resource(ActiveNoise {
+                noise: self.noise,
+                seed: self.seed,
+            })
+            .insert_resource(ActiveTopology(self.topology))
+            .insert_resource(self.size)
+            .insert_resource(Stepper {
+                engine: Engine::Plain,
+                step: 0,
+            })
             .insert_resource(ClearColor(Color::rgb(0.39, 0.58, 0.93)))
             .insert_resource(MousePositionDraw(None))
             .insert_resource(MousePositionErase(None))
             .insert_resource(IsSimulationRunning(false))
-            .insert_resource(EntityRegister { entities: vec![] })
+            .insert_resource(Speed {
+                steps_per_second: 16.0,
+                target: None,
+                owed: 0.0,
+            })
+            .insert_resource(AutoPause(false))
+            .init_resource::<ObjectCensus>()
             .add_system(exit_game)
             .add_system(stop_simulation)
             .add_system(start_simulation)
             .add_system(reset_simulation)
+            .add_system(clear_board)
+            .add_system(change_rule)
+            .add_system(select_draw_state)
+            .add_system(save_session)
+            .add_system(cycle_topology)
+            .add_system(apply_topology)
+            .add_system(toggle_hashlife)
+            .add_system(select_step_size)
+            .add_system(step_keys)
+            .add_system(rewind_timeline.before(CellInteraction::Simulation))
+            .add_system(undo_redo)
+            .add_system(load_session)
+            .add_system(take_census.after(CellInteraction::Simulation))
+            .add_system(export_census.after(take_census))
+            .add_system(apply_cell_layout)
+            .add_system(
+                draw_board
+                    .after(CellInteraction::Setting)
+                    .after(CellInteraction::Simulation)
+            )
             .add_system_set(
                 SystemSet::new()
                     .with_run_criteria(FixedTimestep::step(0.016))
@@ -41,14 +548,10 @@ impl Plugin for SimulationPlugin {
                             .after(CellInteraction::Input)
                     )
             )
-            .add_system_set(
-                SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(0.06))
-                    .with_system(
-                        simulation_step
-                            .label(CellInteraction::Simulation)
-                            .after(CellInteraction::Setting)
-                    )
+            .add_system(
+                simulation_step
+                    .label(CellInteraction::Simulation)
+                    .after(CellInteraction::Setting)
             )
             .add_startup_system(setup);
         //.add_startup_system(setup_samples);
@@ -57,48 +560,45 @@ impl Plugin for SimulationPlugin {
 
 fn setup(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut store: ResMut<EntityRegister>,
+    mut images: ResMut<Assets<Image>>,
+    rule: Res<ActiveRule>,
+    size: Res<BoardSize>,
 ) {
-    for x in 0..GRID_SIZE {
-        for y in 0..GRID_SIZE {
-            let (ass, state) = match check_samples(x, y) {
-                true => {
-                    ("sprites/alive_cell.png", CellState::Alive)
-                }
-                false => {
-                    ("sprites/empty_cell.png", CellState::Empty)
-                }
-            };
-            let e = commands
-                .spawn(
-                    SpriteBundle {
-                        transform: Transform {
-                            translation: Vec3::new((x as f32) * CELL_SIZE, (y as f32) * CELL_SIZE, 0.0),
-                            scale: Vec3::ONE,
-                            ..Default::default()
-                        },
-                        sprite: Sprite {
-                            ..Default::default()
-                        },
-                        texture: asset_server.load(ass),
-                        ..default()
-                    }
-                )
-                .insert(Cell {
-                    state,
-                }).id();
-            store.entities.push(e);
+    let mut universe = Universe::new();
+    for x in 0..size.width {
+        for y in 0..size.height {
+            // the samples sit around (50, 50), the middle of the default board
+            if check_samples(x - size.width / 2 + 50, y - size.height / 2 + 50) {
+                universe.set(x as i64, y as i64, ALIVE);
+            }
         }
     }
 
-    commands
-        .insert_resource(SpriteImages {
-            empty_cell: asset_server.load("sprites/empty_cell.png"),
-            alive_cell: asset_server.load("sprites/alive_cell.png"),
-            dead_cell: asset_server.load("sprites/dead_cell.png"),
-            dying_cell: asset_server.load("sprites/dying_cell.png"),
-        });
+    let mut image = Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::nearest();
+    let image = images.add(image);
+    commands.spawn(SpriteBundle {
+        texture: image.clone(),
+        ..default()
+    }).insert(BoardSprite);
+    commands.insert_resource(BoardImage(image));
+    commands.insert_resource(CellLayout::for_rule(&rule.0, *size));
+    commands.insert_resource(Board {
+        universe,
+        hashlife: None,
+        field: Field::new(size.width, size.height),
+        generation: 0,
+        redraw: HashSet::new(),
+        history: History::new(),
+        timeline: Timeline::new(TIMELINE_LENGTH),
+        start: None,
+        detector: CycleDetector::new(),
+    });
 }
 
 pub fn check_samples(
@@ -172,36 +672,156 @@ fn get_mouse_world_coord(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn cell_interaction(
-    mut cells: Query<(&mut Cell, &mut Handle<Image>, &Transform)>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    layout: Res<CellLayout>,
+    topology: Res<ActiveTopology>,
+    size: Res<BoardSize>,
+    draw_state: Res<DrawState>,
     mut mouse_world_pos_draw: ResMut<MousePositionDraw>,
     mut mouse_world_pos_erase: ResMut<MousePositionErase>,
-    sprite_images: Res<SpriteImages>,
     is_running: Res<IsSimulationRunning>,
 ) {
     let mouse_draw = mouse_world_pos_draw.0.take();
     let mouse_erase = mouse_world_pos_erase.0.take();
-    if !is_running.0 {
-        for (mut cell, mut sprite, transform) in cells.iter_mut() {
-            if let Some(mouse_world_pos) = mouse_draw {
-                if is_in_cell_bounds((mouse_world_pos.0, mouse_world_pos.1), (transform.translation.x, transform.translation.y), (16.0, 16.0)) {
-                    cell.state = CellState::Alive;
-                    *sprite = sprite_images.alive_cell.clone();
+    // a stroke ends when the buttons are let go
+    if mouse_draw.is_none() && mouse_erase.is_none() && board.history.is_open() {
+        board.history.commit();
+    }
+    if is_running.0 {
+        return;
+    }
+
+    let strokes = [(mouse_draw, draw_state.0, 1.0), (mouse_erase, EMPTY, 0.0)];
+    for (mouse_world_pos, state, value) in strokes {
+        let Some(mouse_world_pos) = mouse_world_pos else {
+            continue;
+        };
+        let (x, y) = layout.cell_at(Vec2::new(mouse_world_pos.0, mouse_world_pos.1));
+        match &rule.0 {
+            Automaton::Lenia(lenia) => {
+                if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+                    paint_field(&mut board, x, y, lenia.radius(), value);
                 }
             }
+            rule => {
+                if on_board(x, y, rule, topology.0, *size) {
+                    board.set(x, y, state);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `(x, y)` is a cell the board has: anywhere on the plane, or within
+/// the board's size when it has edges or the rule needs them.
+fn on_board(x: i64, y: i64, rule: &Automaton, topology: Topology, size: BoardSize) -> bool {
+    let bounded = topology.is_bounded() || rule.reach().is_none();
+    !bounded || size.contains(x, y)
+}
+
+/// Paints a disc a quarter of the kernel radius across, so strokes are big
+/// enough for the rule to notice.
+fn paint_field(board: &mut Board, x: i32, y: i32, radius: u8, value: f32) {
+    let r = (radius as i32 / 4).max(1);
+    for dy in -r..=r {
+        for dx in -r..=r {
+            if dx * dx + dy * dy <= r * r {
+                board.paint(x + dx, y + dy, value);
+            }
+        }
+    }
+}
 
-            if let Some(mouse_world_pos) = mouse_erase {
-                if is_in_cell_bounds((mouse_world_pos.0, mouse_world_pos.1), (transform.translation.x, transform.translation.y), (16.0, 16.0)) {
-                    cell.state = CellState::Empty;
-                    *sprite = sprite_images.empty_cell.clone();
+/// How board cells are placed in the world.
+#[derive(Resource, Copy, Clone, PartialEq, Eq)]
+enum CellLayout {
+    Square,
+    /// Pointy-top hexes. The engine stores hex boards skewed, so each row is
+    /// offset half a cell from the one below it, which lines every cell up
+    /// with its six hexagonal neighbours. Row `middle` is not offset at all.
+    Hex { middle: i32 },
+}
+
+impl CellLayout {
+    fn for_rule(rule: &Automaton, size: BoardSize) -> Self {
+        if rule.is_hexagonal() {
+            CellLayout::Hex { middle: size.height / 2 }
+        } else {
+            CellLayout::Square
+        }
+    }
+
+    fn row_height(self) -> f32 {
+        match self {
+            CellLayout::Square => CELL_SIZE,
+            CellLayout::Hex { .. } => CELL_SIZE * 3.0_f32.sqrt() / 2.0,
+        }
+    }
+
+    /// Row offset in cells; zero for the middle row of a hex board.
+    fn row_shift(self, y: f32) -> f32 {
+        match self {
+            CellLayout::Square => 0.0,
+            CellLayout::Hex { middle } => (middle as f32 - y) / 2.0,
+        }
+    }
+
+    /// Texels across a cell in the board image. Hex rows sit half a cell
+    /// apart, so they need two.
+    fn texels_per_cell(self) -> i64 {
+        match self {
+            CellLayout::Square => 1,
+            CellLayout::Hex { .. } => 2,
+        }
+    }
+
+    fn texel_width(self) -> f32 {
+        CELL_SIZE / self.texels_per_cell() as f32
+    }
+
+    /// The column of the cell under texel column `u` of row `y`, where
+    /// column `u` starts at `u * texel_width()` less half a cell.
+    fn texel_cell(self, u: i64, y: i64) -> i64 {
+        match self {
+            CellLayout::Square => u,
+            CellLayout::Hex { middle } => (u - middle as i64 + y).div_euclid(2),
+        }
+    }
+
+    fn cell_at(self, world_pos: Vec2) -> (i64, i64) {
+        let y = world_pos.y / self.row_height();
+        let x = world_pos.x / CELL_SIZE - self.row_shift(y);
+        match self {
+            CellLayout::Square => (x.round() as i64, y.round() as i64),
+            CellLayout::Hex { .. } => {
+                // round in cube coordinates, where hex distance is well behaved
+                let (q, r) = (x - y, y);
+                let s = -q - r;
+                let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+                let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+                if dq > dr && dq > ds {
+                    rq = -rr - rs;
+                } else if dr > ds {
+                    rr = -rq - rs;
                 }
+                ((rq + rr) as i64, rr as i64)
             }
         }
     }
 }
 
-fn is_in_cell_bounds(xy: (f32, f32), center: (f32, f32), dims: (f32, f32)) -> bool {
-    xy.0 >= center.0 - dims.0 && xy.0 < center.0 + dims.0 && xy.1 >= center.1 - dims.1 && xy.1 < center.1 + dims.1
+fn apply_cell_layout(rule: Res<ActiveRule>, size: Res<BoardSize>, mut layout: ResMut<CellLayout>) {
+    if !rule.is_changed() && !size.is_changed() {
+        return;
+    }
+
+    let new_layout = CellLayout::for_rule(&rule.0, *size);
+    if *layout != new_layout {
+        *layout = new_layout;
+    }
 }
 
 fn exit_game(
@@ -216,32 +836,363 @@ fn exit_game(
 fn start_simulation(
     mut event_reader: EventReader<StartSimulationEvent>,
     mut start: ResMut<IsSimulationRunning>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
 ) {
-    if event_reader.iter().next().is_some() {
+    if event_reader.iter().next().is_some() && !start.0 {
         start.0 = true;
+        board.settle();
+        let frame = board.frame(rule.0.is_continuous());
+        board.start = Some((board.generation, frame));
     }
 }
 
 fn stop_simulation(
     mut event_reader: EventReader<StopSimulationEvent>,
     mut start: ResMut<IsSimulationRunning>,
+    mut speed: ResMut<Speed>,
 ) {
     if event_reader.iter().next().is_some() {
         start.0 = false;
+        speed.target = None;
+    }
+}
+
+fn change_rule(
+    mut event_reader: EventReader<ChangeRuleEvent>,
+    mut active_rule: ResMut<ActiveRule>,
+    mut active_noise: ResMut<ActiveNoise>,
+    mut active_topology: ResMut<ActiveTopology>,
+    mut board_size: ResMut<BoardSize>,
+    mut board: ResMut<Board>,
+    mut draw_state: ResMut<DrawState>,
+) {
+    if let Some(ChangeRuleEvent(line)) = event_reader.iter().last() {
+        let states = line.rule.states();
+        active_rule.0 = line.rule.clone();
+        active_noise.noise = line.noise;
+        if let Some(seed) = line.seed {
+            active_noise.seed = seed;
+        }
+        if let Some(topology) = line.topology {
+            active_topology.0 = topology;
+        }
+        match line.size {
+            Some(size) if size != *board_size => *board_size = size,
+            _ => {}
+        }
+        board.settle();
+        board.universe.clamp_states(states);
+        if draw_state.0 >= states {
+            draw_state.0 = ALIVE;
+        }
+    }
+}
+
+fn save_session(
+    mut event_reader: EventReader<SaveSessionEvent>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    noise: Res<ActiveNoise>,
+    topology: Res<ActiveTopology>,
+    size: Res<BoardSize>,
+) {
+    if event_reader.iter().next().is_none() {
+        return;
+    }
+
+    board.settle();
+    // a board with edges is saved whole, the plane just around its cells
+    let (x0, y0, x1, y1) = match board.universe.bounds() {
+        Some(bounds) if !topology.0.is_bounded() => bounds,
+        _ => (0, 0, size.width as i64 - 1, size.height as i64 - 1),
+    };
+    let session = Session {
+        rule: rule_line(&rule.0, &noise.noise, topology.0, *size),
+        seed: noise.seed,
+        generation: board.generation,
+        grid: board.universe.window(x0, y0, (x1 - x0 + 1) as i32, (y1 - y0 + 1) as i32),
+        origin: (x0, y0),
+    };
+    match session.save(SESSION_FILE) {
+        Ok(()) => println!("Saved session to {SESSION_FILE}."),
+        Err(e) => eprintln!("Could not save session: {e}"),
+    }
+}
+
+fn load_session(
+    mut event_reader: EventReader<LoadSessionEvent>,
+    mut board: ResMut<Board>,
+    mut active_rule: ResMut<ActiveRule>,
+    mut active_noise: ResMut<ActiveNoise>,
+    mut active_topology: ResMut<ActiveTopology>,
+    mut board_size: ResMut<BoardSize>,
+    mut draw_state: ResMut<DrawState>,
+) {
+    if event_reader.iter().next().is_none() {
+        return;
+    }
+
+    let loaded = Session::load(SESSION_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|session| Ok((RuleLine::parse(&session.rule)?, session)));
+    let (line, session) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Could not load session: {e}");
+            return;
+        }
+    };
+
+    let topology = line.topology.unwrap_or(Topology::Plane);
+    let size = line.size.unwrap_or(*board_size);
+    let (x0, y0) = session.origin;
+    let mut universe = Universe::new();
+    for (x, y, state) in session.grid.iter() {
+        let (x, y) = (x0 + x as i64, y0 + y as i64);
+        if state != EMPTY && on_board(x, y, &line.rule, topology, size) {
+            universe.set(x, y, state);
+        }
+    }
+    universe.clamp_states(line.rule.states());
+    board.hashlife = None;
+    board.replace(universe, &Field::new(size.width, size.height));
+    board.timeline.clear();
+    board.generation = session.generation;
+    active_rule.0 = line.rule;
+    active_noise.noise = line.noise;
+    active_noise.seed = session.seed;
+    active_topology.0 = topology;
+    if size != *board_size {
+        *board_size = size;
+    }
+    draw_state.0 = ALIVE;
+    println!("Loaded session from {SESSION_FILE}.");
+}
+
+/// Counts the objects on the board, naming the common ones when the rule is
+/// Life. Rules that don't work cell by cell have no objects to count.
+fn take_census(
+    mut event_reader: EventReader<TakeCensusEvent>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    mut census: ResMut<ObjectCensus>,
+) {
+    if event_reader.iter().next().is_none() {
+        return;
+    }
+    let Some(reach) = rule.0.reach() else {
+        eprintln!("A census needs a rule that works cell by cell.");
+        return;
+    };
+
+    board.settle();
+    let library = match &rule.0 {
+        Automaton::LifeLike(rule) if *rule == Rule::life() => Library::life(),
+        _ => Library::default(),
+    };
+    census.0 = Some((board.generation, Census::take(&board.universe, &rule.0, reach, &library)));
+}
+
+fn export_census(
+    mut event_reader: EventReader<ExportCensusEvent>,
+    census: Res<ObjectCensus>,
+) {
+    if event_reader.iter().next().is_none() {
+        return;
+    }
+
+    let Some((_, census)) = &census.0 else {
+        eprintln!("Take a census before exporting it.");
+        return;
+    };
+    match std::fs::write(CENSUS_FILE, census.to_csv()) {
+        Ok(()) => println!("Saved census to {CENSUS_FILE}."),
+        Err(e) => eprintln!("Could not save census: {e}"),
+    }
+}
+
+fn cycle_topology(
+    mut event_reader: EventReader<CycleTopologyEvent>,
+    mut active_topology: ResMut<ActiveTopology>,
+) {
+    for _ in event_reader.iter() {
+        active_topology.0 = active_topology.0.next();
     }
 }
 
+/// Keeps the board's edges in step with the chosen topology and size,
+/// dropping cells that fall outside a bounded board.
+fn apply_topology(
+    active_topology: Res<ActiveTopology>,
+    size: Res<BoardSize>,
+    rule: Res<ActiveRule>,
+    mut board: ResMut<Board>,
+) {
+    if !active_topology.is_changed() && !size.is_changed() && !rule.is_changed() {
+        return;
+    }
+
+    board.settle();
+    if active_topology.0.is_bounded() || rule.0.reach().is_none() {
+        board.universe.crop(0, 0, size.width as i64, size.height as i64);
+    }
+    if (board.field.width(), board.field.height()) != (size.width, size.height) {
+        // a resized field keeps the values the two sizes share
+        let mut field = Field::new(size.width, size.height);
+        for y in 0..size.height.min(board.field.height()) {
+            for x in 0..size.width.min(board.field.width()) {
+                field.set(x, y, board.field.get(x, y));
+            }
+        }
+        board.field = field;
+    }
+    // still areas may not stay still under the new rule or edges
+    board.universe.touch_all();
+    board.detector.forget();
+    board.field.set_topology(active_topology.0);
+}
+
+fn toggle_hashlife(mut event_reader: EventReader<ToggleHashLifeEvent>, mut stepper: ResMut<Stepper>) {
+    for _ in event_reader.iter() {
+        stepper.engine = match stepper.engine {
+            Engine::Plain => Engine::HashLife,
+            Engine::HashLife => Engine::Plain,
+        };
+    }
+}
+
+/// `[` and `]` halve and double how far HashLife jumps per tick.
+fn select_step_size(
+    keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
+    mut stepper: ResMut<Stepper>,
+) {
+    if rule_input.is_editing() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::LBracket) && stepper.step > 0 {
+        stepper.step -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::RBracket) && stepper.step < MAX_STEP {
+        stepper.step += 1;
+    }
+}
+
+/// `N` advances one step and `B` steps back, running or not.
+fn step_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
+    mut step_writer: EventWriter<StepOnceEvent>,
+    mut back_writer: EventWriter<StepBackEvent>,
+) {
+    if rule_input.is_editing() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::N) {
+        step_writer.send(StepOnceEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::B) {
+        back_writer.send(StepBackEvent);
+    }
+}
+
+/// Pauses and puts back a past generation from the timeline. Pressing Play
+/// from there, edited or not, forks a new run.
+fn rewind_timeline(
+    mut back_reader: EventReader<StepBackEvent>,
+    mut rewind_reader: EventReader<RewindEvent>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    topology: Res<ActiveTopology>,
+    mut is_running: ResMut<IsSimulationRunning>,
+    mut speed: ResMut<Speed>,
+) {
+    let steps_back = back_reader.iter().count();
+    let rewind_to = rewind_reader.iter().last();
+    if steps_back == 0 && rewind_to.is_none() {
+        return;
+    }
+
+    is_running.0 = false;
+    speed.target = None;
+    let continuous = rule.0.is_continuous();
+    if let Some(RewindEvent(i)) = rewind_to {
+        board.rewind(*i, continuous);
+    }
+    for _ in 0..steps_back {
+        board.step_back(continuous);
+    }
+    board.field.set_topology(topology.0);
+}
+
+/// `Ctrl+Z` undoes the last edit and `Ctrl+Shift+Z` redoes it.
+fn undo_redo(
+    keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
+    mut board: ResMut<Board>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if rule_input.is_editing() || !ctrl || !keyboard_input.just_pressed(KeyCode::Z) {
+        return;
+    }
+
+    if keyboard_input.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        board.redo();
+    } else {
+        board.undo();
+    }
+}
+
+fn select_draw_state(
+    keyboard_input: Res<Input<KeyCode>>,
+    rule_input: Res<RuleInput>,
+    rule: Res<ActiveRule>,
+    mut draw_state: ResMut<DrawState>,
+) {
+    if rule_input.is_editing() {
+        return;
+    }
+
+    let keys = [
+        KeyCode::Key1, KeyCode::Key2, KeyCode::Key3,
+        KeyCode::Key4, KeyCode::Key5, KeyCode::Key6,
+        KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    ];
+    for (state, key) in (1..).zip(keys) {
+        if keyboard_input.just_pressed(key) && state < rule.0.states() {
+            draw_state.0 = state;
+        }
+    }
+}
+
+/// Stops the run and puts back the board from when Start was last pressed.
 fn reset_simulation(
-    mut event_reader: EventReader<ResetSimulationEvent>
+    mut event_reader: EventReader<ResetSimulationEvent>,
+    mut board: ResMut<Board>,
+    mut is_running: ResMut<IsSimulationRunning>,
+    mut speed: ResMut<Speed>,
 ) {
     if event_reader.iter().next().is_some() {
-        println!("Requesting simulation reset.")
+        is_running.0 = false;
+        speed.target = None;
+        board.reset();
     }
 }
 
-#[derive(Resource)]
-struct EntityRegister {
-    entities: Vec<Entity>,
+/// Stops the run and empties the board.
+fn clear_board(
+    mut event_reader: EventReader<ClearBoardEvent>,
+    mut board: ResMut<Board>,
+    mut is_running: ResMut<IsSimulationRunning>,
+    mut speed: ResMut<Speed>,
+) {
+    if event_reader.iter().next().is_some() {
+        is_running.0 = false;
+        speed.target = None;
+        board.clear();
+    }
 }
 
 #[derive(Resource)]
@@ -250,18 +1201,13 @@ struct MousePositionDraw(Option<(f32, f32)>);
 #[derive(Resource)]
 struct MousePositionErase(Option<(f32, f32)>);
 
-#[derive(Component)]
-struct Cell {
-    state: CellState,
-}
+/// The texture the cells in view are drawn into.
+#[derive(Resource)]
+struct BoardImage(Handle<Image>);
 
-#[derive(PartialEq)]
-enum CellState {
-    Alive,
-    Dead,
-    Dying,
-    Empty,
-}
+/// The sprite showing [`BoardImage`].
+#[derive(Component)]
+struct BoardSprite;
 
 #[derive(Component)]
 struct MarkForDeath {
@@ -271,71 +1217,877 @@ struct MarkForDeath {
 #[derive(Resource)]
 struct IsSimulationRunning(bool);
 
-#[derive(Resource)]
-struct SpriteImages {
-    empty_cell: Handle<Image>,
-    alive_cell: Handle<Image>,
-    dead_cell: Handle<Image>,
-    dying_cell: Handle<Image>,
+/// Colour of a cell in `state`, from the rule's colour for it.
+fn state_colour(state: CellState, colours: &[[u8; 3]]) -> [u8; 4] {
+    match (state, colours.get(state as usize)) {
+        (EMPTY, _) => EMPTY_COLOUR,
+        (_, None) => [255; 4],
+        (_, Some(&[r, g, b])) => [r, g, b, 255],
+    }
 }
 
+/// Runs as many steps as the speed asks for since the last frame, a single
+/// one when asked to, or as many as fit in the frame while running to a
+/// target generation. Steps that don't fit in [`FRAME_BUDGET`] are dropped
+/// rather than owed, so a slow rule slows the simulation, not the frame rate.
+#[allow(clippy::too_many_arguments)]
 fn simulation_step(
-    mut commands: Commands,
-    mut cells: Query<(Entity, &mut Cell, &mut Handle<Image>, Option<&MarkForDeath>), With<Cell>>,
-    is_running: Res<IsSimulationRunning>,
-    sprite_images: Res<SpriteImages>,
-    mut store: ResMut<EntityRegister>,
-    mut ew: EventWriter<CellMarkedForDeathEvent>,
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    noise: Res<ActiveNoise>,
+    topology: Res<ActiveTopology>,
+    size: Res<BoardSize>,
+    stepper: Res<Stepper>,
+    mut is_running: ResMut<IsSimulationRunning>,
+    mut speed: ResMut<Speed>,
+    time: Res<Time>,
+    auto_pause: Res<AutoPause>,
+    mut step_once: EventReader<StepOnceEvent>,
+    mut run_to: EventReader<RunToEvent>,
+    mut census_writer: EventWriter<TakeCensusEvent>,
 ) {
-    if is_running.0 {
-        let mut life_grid: Vec<bool> = Vec::new();
-        for (_, cell, _sprite, mfd) in cells.iter_mut() {
-            life_grid.push(match cell.state {
-                CellState::Alive => true,
-                CellState::Dead | CellState::Empty | CellState::Dying => false,
-            });
+    if let Some(RunToEvent(target)) = run_to.iter().last() {
+        speed.target = Some(*target).filter(|&target| target > board.generation);
+        is_running.0 = speed.target.is_some();
+    }
+
+    let mut steps = step_once.iter().count() as u64;
+    // the carried-over fraction changes every frame, but nobody shows it
+    let pace = speed.bypass_change_detection();
+    if !is_running.0 {
+        pace.owed = 0.0;
+    } else if pace.target.is_some() {
+        steps = u64::MAX;
+    } else {
+        pace.owed += pace.steps_per_second * time.delta_seconds();
+        steps += pace.owed as u64;
+        pace.owed = pace.owed.fract();
+    }
+
+    let started = Instant::now();
+    for _ in 0..steps {
+        let remaining = speed.target.map(|target| target.saturating_sub(board.generation));
+        if remaining == Some(0) {
+            speed.target = None;
+            is_running.0 = false;
+            break;
         }
+        let fate = advance(&mut board, &rule.0, &noise, topology.0, *size, &stepper, remaining);
+        if matches!(fate, Some(Fate::Still { .. } | Fate::Oscillating { .. })) {
+            census_writer.send(TakeCensusEvent);
+        }
+        if fate.is_some() && auto_pause.0 {
+            speed.target = None;
+            is_running.0 = false;
+            break;
+        }
+        if started.elapsed() > FRAME_BUDGET {
+            speed.bypass_change_detection().owed = 0.0;
+            break;
+        }
+    }
+}
 
-        for (ind, (entity, mut cell, mut sprite, mfd)) in cells.iter_mut().enumerate() {
-            let mut neighbour_cnt = 0;
-            let x = ind as i32 % GRID_SIZE;
-            let y = ind as i32 / GRID_SIZE;
+/// Advances the board one step: a generation, or `2^k` of them with
+/// HashLife, though never more than `limit`. Returns the run's fate if the
+/// step is where it was found. HashLife runs are watched and recorded a jump
+/// at a time, so their fates are dated to the jump they were seen at.
+fn advance(
+    board: &mut Board,
+    rule: &Automaton,
+    noise: &ActiveNoise,
+    topology: Topology,
+    size: BoardSize,
+    stepper: &Stepper,
+    limit: Option<u64>,
+) -> Option<Fate> {
+    let hashing = stepper.engine == Engine::HashLife && Stepper::can_hash(rule, &noise.noise, topology);
+    if hashing {
+        // the board the first jump starts from is watched and kept as well
+        let found = if board.hashlife.is_none() {
+            board.record(false);
+            board.observe()
+        } else {
+            None
+        };
+        let universe = &board.universe;
+        let hashlife = board
+            .hashlife
+            .get_or_insert_with(|| HashLife::from_universe(rule.clone(), universe));
+        let k = limit.map_or(stepper.step, |limit| stepper.step.min(limit.ilog2() as u8));
+        hashlife.step(k);
+        board.generation += 1 << k;
 
-            for xi in (x - 1)..(x + 2) {
-                for yi in (y - 1)..(y + 2) {
-                    if (xi != x || yi != y) && xi >= 0 && xi < GRID_SIZE && yi >= 0 && yi < GRID_SIZE {
-                        let lin_ind = xi + yi * GRID_SIZE;
-                        if life_grid[lin_ind as usize] {
-                            neighbour_cnt += 1;
-                        }
+        let universe = hashlife.to_universe();
+        let frame = Frame { cells: Snapshot::of(&universe), field: None };
+        board.timeline.record(board.generation, frame);
+        if found.is_some() {
+            return found;
+        }
+        return match board.detector.observe(board.generation, universe.zobrist(), universe.is_empty()) {
+            Some(Fate::Oscillating { period, .. }) => board.detector.narrow(shortest_period(universe, rule, period)),
+            fate => fate,
+        };
+    }
+    board.settle();
+    board.record(rule.is_continuous());
+    // a noisy run can leave a repeated board differently, and Lenia never
+    // quite repeats
+    let watched = !noise.noise.is_random() && !rule.is_continuous();
+    let found = if watched { board.observe() } else { None };
+    if noise.noise.is_random() {
+        board.universe.touch_all();
+    }
+
+    let (seed, generation) = (noise.seed, board.generation);
+    match (rule, rule.reach()) {
+        (Automaton::Lenia(lenia), _) => board.field.step(lenia),
+        (rule, Some(reach)) if !topology.is_bounded() => {
+            // each row of chunks is a band, stepped as a task of its own
+            let universe = &board.universe;
+            let active = universe.active_chunks(reach);
+            let next_states = |grid: &Grid, chunk| {
+                let seed = Rng::region_seed(seed, chunk);
+                noise.noise.over(rule, rule.states(), seed, generation).next_states(grid)
+            };
+            let bands = ComputeTaskPool::get().scope(|scope| {
+                for band in active.chunk_by(|a, b| a.1 == b.1) {
+                    let next_states = &next_states;
+                    scope.spawn(async move {
+                        band.iter()
+                            .map(|&chunk| (chunk, universe.next_chunk(chunk, reach, next_states)))
+                            .collect::<Vec<_>>()
+                    });
+                }
+            });
+            board.universe.update_chunks(bands.into_iter().flatten());
+        }
+        (rule, Some(reach)) => {
+            // bounded boards run in bands of rows that each carry a margin of
+            // the rows around them, about one band to a thread
+            let BoardSize { width, height } = size;
+            let grid = board.universe.window(0, 0, width, height).with_topology(topology);
+            let pad = reach as i32;
+            let changed: HashSet<_> = board.universe.changed_chunks().collect();
+            // noise and B0 change empty cells too, which no chunk lists
+            let restless = noise.noise.is_random() || rule.is_born_from_nothing();
+            let pool = ComputeTaskPool::get();
+            let band_height = band_height(height, pool.thread_num());
+            let bands = pool.scope(|scope| {
+                for y0 in (0..height).step_by(band_height as usize) {
+                    // a band whose cells and margin stood still stays as it is
+                    let rows = band_height.min(height - y0);
+                    let read = (y0 - pad..y0 + rows + pad).flat_map(|y| (-pad..width + pad).map(move |x| (x, y)));
+                    let stirred = restless || read
+                        .filter_map(|(x, y)| topology.resolve(x, y, width, height))
+                        .any(|(x, y)| changed.contains(&chunk_of(x as i64, y as i64)));
+                    if !stirred {
+                        continue;
                     }
+                    let grid = &grid;
+                    let noise = &noise.noise;
+                    scope.spawn(async move {
+                        let band = grid.band(y0, rows, pad);
+                        let seed = Rng::region_seed(seed, (0, y0 as i64));
+                        let next = noise.over(rule, rule.states(), seed, generation).next_states(&band);
+                        let cells: Vec<_> = next
+                            .chunks_exact(band.width() as usize)
+                            .skip(pad as usize)
+                            .take(rows as usize)
+                            .flat_map(|row| &row[pad as usize..(pad + width) as usize])
+                            .copied()
+                            .collect();
+                        (y0, cells)
+                    });
                 }
+            });
+            let mut grid = grid;
+            for (y0, cells) in bands {
+                grid.set_rows(y0, &cells);
             }
+            board.universe.forget_changes();
+            board.universe.paste(&grid, 0, 0);
+        }
+        (rule, None) => {
+            // rules that see the whole row at once can't be split up
+            let mut grid = board.universe.window(0, 0, size.width, size.height).with_topology(topology);
+            grid.step(&noise.noise.over(rule, rule.states(), seed, generation));
+            board.universe.forget_changes();
+            board.universe.paste(&grid, 0, 0);
+        }
+    }
+    board.generation += 1;
+    let changed: Vec<_> = board.universe.changed_chunks().collect();
+    board.redraw.extend(changed);
+    if watched {
+        found.or_else(|| board.observe())
+    } else {
+        None
+    }
+}
+
+/// The period of `universe` under `rule`, which comes back every `period`
+/// generations, found by stepping it at most [`MAX_PERIOD`] generations on.
+fn shortest_period(mut universe: Universe, rule: &Automaton, period: u64) -> u64 {
+    let hash = universe.zobrist();
+    for p in 1..period.min(MAX_PERIOD) {
+        universe.step(rule, 1);
+        if universe.zobrist() == hash {
+            return p;
+        }
+    }
+    period
+}
+
+/// Rows in each band of a bounded board `height` rows tall, so the bands go
+/// round `threads` threads about once.
+fn band_height(height: i32, threads: usize) -> i32 {
+    (height as usize).div_ceil(threads.max(1)).max(MIN_BAND_HEIGHT as usize) as i32
+}
 
-            match cell.state {
-                CellState::Dying => {
-                    cell.state = CellState::Dead;
-                    *sprite = sprite_images.dead_cell.clone();
+type BoardSpriteItem = (&'static mut Transform, &'static mut Sprite);
+
+/// Draws the cells in view into the board image and stretches its sprite
+/// over them, one texel per cell, or two across on hex boards so rows can sit
+/// half a cell apart. Between moves of the camera only the chunks the board
+/// lists for redrawing are written. Cells the board lacks, beyond a bounded
+/// board's edges, are left clear. Once cells are smaller than a pixel, each
+/// texel instead shades a block of cells by how full it is; see
+/// [`draw_blocks`].
+#[allow(clippy::too_many_arguments)]
+fn draw_board(
+    mut board: ResMut<Board>,
+    rule: Res<ActiveRule>,
+    topology: Res<ActiveTopology>,
+    size: Res<BoardSize>,
+    layout: Res<CellLayout>,
+    windows: Res<Windows>,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut sprite: Query<BoardSpriteItem, (With<BoardSprite>, Without<MainCamera>)>,
+    board_image: Res<BoardImage>,
+    mut images: ResMut<Assets<Image>>,
+    mut shown: Local<Option<View>>,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let (camera_transform, projection) = camera.single();
+    let view = View::visible(*layout, camera_transform, projection, window);
+    // HashLife and continuous rules don't say which cells changed
+    let untracked = board.is_changed() && (board.hashlife.is_some() || rule.0.is_continuous());
+    let whole = untracked
+        || rule.is_changed()
+        || topology.is_changed()
+        || size.is_changed()
+        || layout.is_changed()
+        || *shown != Some(view);
+    if !whole && board.redraw.is_empty() {
+        return;
+    }
+    let redraw = std::mem::take(&mut board.bypass_change_detection().redraw);
+    // blocks don't line up with chunks, so they are all drawn again
+    let whole = whole || view.level > 0;
+    let Some(image) = images.get_mut(&board_image.0) else {
+        return;
+    };
+
+    if *shown != Some(view) {
+        let size = Extent3d {
+            width: view.width as u32,
+            height: view.height as u32,
+            depth_or_array_layers: 1,
+        };
+        if image.texture_descriptor.size != size {
+            image.resize(size);
+        }
+        let (mut transform, mut sprite) = sprite.single_mut();
+        let (centre, size) = view.world_rect(*layout);
+        transform.translation = centre.extend(0.0);
+        sprite.custom_size = Some(size);
+        *shown = Some(view);
+    }
+
+    let colours = rule.0.colours();
+    let (board, rule, topology, size, layout) = (&*board, &rule.0, topology.0, *size, *layout);
+    if view.level > 0 {
+        draw_blocks(view, board, rule, topology, size, layout, &colours, &mut image.data);
+        return;
+    }
+    let colours = &colours;
+    let redraw = &redraw;
+    let row_bytes = view.width as usize * 4;
+    ComputeTaskPool::get().scope(|scope| {
+        for (band, rows) in image.data.chunks_mut(row_bytes * IMAGE_BAND).enumerate() {
+            scope.spawn(async move {
+                for (j, row) in rows.chunks_exact_mut(row_bytes).enumerate() {
+                    // the image's first row is the top one
+                    let y = view.y0 + view.height - 1 - (band * IMAGE_BAND + j) as i64;
+                    for (i, texel) in row.chunks_exact_mut(4).enumerate() {
+                        let x = layout.texel_cell(view.u0 + i as i64, y);
+                        if whole || redraw.contains(&chunk_of(x, y)) {
+                            texel.copy_from_slice(&cell_colour(board, rule, topology, size, colours, x, y));
+                        }
+                    }
                 }
-                _ => {}
+            });
+        }
+    });
+}
+
+/// Colour of the cell at `(x, y)`, or clear if the board has no such cell.
+fn cell_colour(
+    board: &Board,
+    rule: &Automaton,
+    topology: Topology,
+    size: BoardSize,
+    colours: &[[u8; 3]],
+    x: i64,
+    y: i64,
+) -> [u8; 4] {
+    if rule.is_continuous() {
+        if size.contains(x, y) { ramp_colour(board.field.get(x as i32, y as i32)) } else { [0; 4] }
+    } else if on_board(x, y, rule, topology, size) {
+        state_colour(board.get(x, y), colours)
+    } else {
+        [0; 4]
+    }
+}
+
+/// Draws the view one block of `2^level` cells to a texel, shaded from the
+/// empty colour to the colour of live cells by the share of the block that
+/// is filled. The cells are counted from the board's chunks, or whole nodes
+/// of HashLife's tree, so the cost follows what is on the board and the
+/// number of texels, never the area in view.
+#[allow(clippy::too_many_arguments)]
+fn draw_blocks(
+    view: View,
+    board: &Board,
+    rule: &Automaton,
+    topology: Topology,
+    size: BoardSize,
+    layout: CellLayout,
+    colours: &[[u8; 3]],
+    data: &mut [u8],
+) {
+    let block = 1i64 << view.level;
+    let (width, height) = (view.width as usize, view.height as usize);
+    let mut filled = vec![0f32; width * height];
+    let mut add = |bx: i64, by: i64, amount: f32| {
+        // hex blocks go where their middle cell is drawn
+        let middle = (block - 1) as f32 / 2.0;
+        let x = (bx * block) as f32 + middle + layout.row_shift((by * block) as f32 + middle);
+        let (i, j) = (((x + 0.5) / block as f32).floor() as i64 - view.u0, by - view.y0);
+        if (0..view.width).contains(&i) && (0..view.height).contains(&j) {
+            filled[i as usize + j as usize * width] += amount;
+        }
+    };
+
+    let bounded = rule.is_continuous() || topology.is_bounded() || rule.reach().is_none();
+    let (y0, y1) = (view.y0 * block, (view.y0 + view.height) * block - 1);
+    let shifts = [layout.row_shift(y0 as f32), layout.row_shift(y1 as f32)];
+    let (low, high) = (shifts[0].min(shifts[1]), shifts[0].max(shifts[1]));
+    let x0 = (view.u0 * block) as f32 - 0.5 - high;
+    let x1 = ((view.u0 + view.width) * block) as f32 - 0.5 - low;
+    let mut rect = (x0.floor() as i64, y0, x1.ceil() as i64, y1);
+    if bounded {
+        rect = (rect.0.max(0), rect.1.max(0), rect.2.min(size.width as i64 - 1), rect.3.min(size.height as i64 - 1));
+    }
+    if rule.is_continuous() {
+        for y in rect.1..=rect.3 {
+            for x in rect.0..=rect.2 {
+                add(x >> view.level, y >> view.level, board.field.get(x as i32, y as i32));
             }
+        }
+    } else {
+        let count = |bx, by, n| add(bx, by, n as f32);
+        match &board.hashlife {
+            Some(hashlife) => hashlife.filled_blocks(view.level, rect, count),
+            None => board.universe.filled_blocks(view.level, rect, count),
+        }
+    }
 
-            if neighbour_cnt < 2 || neighbour_cnt > 3 {
-                match cell.state {
-                    CellState::Alive => {
-                        cell.state = CellState::Dying;
-                        *sprite = sprite_images.dying_cell.clone();
+    let live = state_colour(ALIVE, colours);
+    let area = (block * block) as f32;
+    let row_bytes = width * 4;
+    ComputeTaskPool::get().scope(|scope| {
+        for (band, rows) in data.chunks_mut(row_bytes * IMAGE_BAND).enumerate() {
+            let filled = &filled;
+            scope.spawn(async move {
+                for (j, row) in rows.chunks_exact_mut(row_bytes).enumerate() {
+                    // the image's first row is the top one
+                    let j = height - 1 - (band * IMAGE_BAND + j);
+                    let by = view.y0 + j as i64;
+                    for (i, texel) in row.chunks_exact_mut(4).enumerate() {
+                        let shift = layout.row_shift(((by * block) as f32) + block as f32 / 2.0);
+                        let left = ((view.u0 + i as i64) * block) as f32 - 0.5 - shift;
+                        let (xs, ys) = (left.floor() as i64..(left + block as f32).ceil() as i64, by * block..(by + 1) * block);
+                        let (right, top) = (size.width as i64, size.height as i64);
+                        let on_board = !bounded || xs.start < right && xs.end > 0 && ys.start < top && ys.end > 0;
+                        let share = filled[i + j * width] / area;
+                        let colour = if !on_board {
+                            [0; 4]
+                        } else if rule.is_continuous() {
+                            ramp_colour(share)
+                        } else {
+                            // a square root keeps thin patterns visible
+                            let t = share.sqrt();
+                            std::array::from_fn(|c| (EMPTY_COLOUR[c] as f32 + (live[c] as f32 - EMPTY_COLOUR[c] as f32) * t) as u8)
+                        };
+                        texel.copy_from_slice(&colour);
                     }
-                    CellState::Dead | CellState::Empty | CellState::Dying => {}
+                }
+            });
+        }
+    });
+}
+
+/// The part of the world the board image covers: texel columns
+/// `u0..u0 + width` and rows `y0..y0 + height`. At level 0 a texel is one
+/// cell, or half a hex cell; above that it is a block of `2^level` cells
+/// either way.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct View {
+    u0: i64,
+    y0: i64,
+    width: i64,
+    height: i64,
+    level: u8,
+}
+
+impl View {
+    /// The texels the camera can see, with one of margin all round, keeping
+    /// to [`MAX_IMAGE_SIZE`] around the middle. Blocks are as small as they
+    /// can be while still a pixel or more across.
+    fn visible(
+        layout: CellLayout,
+        transform: &Transform,
+        projection: &OrthographicProjection,
+        window: &Window,
+    ) -> Self {
+        let centre = transform.translation.truncate();
+        let half = Vec2::new(window.width(), window.height()) / 2.0 * projection.scale;
+        let cells_per_pixel = projection.scale / CELL_SIZE;
+        let level = if cells_per_pixel > 1.0 { cells_per_pixel.log2().ceil() as u8 } else { 0 };
+        let probe = Self { u0: 0, y0: 0, width: 0, height: 0, level };
+        let column = |x: f32| ((x + CELL_SIZE / 2.0) / probe.texel_width(layout)).floor() as i64;
+        let row = |y: f32| ((y / layout.row_height() + 0.5) / (1 << level) as f32).floor() as i64;
+        let span = |low: i64, high: i64| {
+            let (low, high) = (low - 1, high + 1);
+            let excess = (high - low + 1 - MAX_IMAGE_SIZE).max(0);
+            (low + excess / 2, high - low + 1 - excess)
+        };
+        let (u0, width) = span(column(centre.x - half.x), column(centre.x + half.x));
+        let (y0, height) = span(row(centre.y - half.y), row(centre.y + half.y));
+        Self { u0, y0, width, height, level }
+    }
+
+    fn texel_width(self, layout: CellLayout) -> f32 {
+        match self.level {
+            0 => layout.texel_width(),
+            level => CELL_SIZE * (1 << level) as f32,
+        }
+    }
+
+    fn row_height(self, layout: CellLayout) -> f32 {
+        layout.row_height() * (1 << self.level) as f32
+    }
+
+    /// The centre and size of the view in the world.
+    fn world_rect(self, layout: CellLayout) -> (Vec2, Vec2) {
+        let size = Vec2::new(self.width as f32 * self.texel_width(layout), self.height as f32 * self.row_height(layout));
+        let corner = Vec2::new(
+            self.u0 as f32 * self.texel_width(layout) - CELL_SIZE / 2.0,
+            self.y0 as f32 * self.row_height(layout) - layout.row_height() / 2.0,
+        );
+        (corner + size / 2.0, size)
+    }
+}
+
+/// Colour for a continuous cell value: near black at 0, through blue and
+/// green to pale yellow at 1.
+fn ramp_colour(value: f32) -> [u8; 4] {
+    const STOPS: [[f32; 3]; 4] = [[0.1, 0.1, 0.125], [0.16, 0.35, 0.78], [0.6, 0.9, 0.31], [1.0, 0.94, 0.47]];
+    let t = value.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f32;
+    let [r, g, b] = std::array::from_fn(|c| ((STOPS[i][c] + (STOPS[i + 1][c] - STOPS[i][c]) * f) * 255.0) as u8);
+    [r, g, b, 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::tasks::TaskPool;
+
+    const GLIDER: [(i64, i64); 5] = [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)];
+
+    /// A board holding `cells`, with a field of `size`, and the compute pool
+    /// the stepper and drawing run on.
+    fn board(cells: &[(i64, i64)], size: BoardSize) -> Board {
+        ComputeTaskPool::init(TaskPool::default);
+        let mut universe = Universe::new();
+        for &(x, y) in cells {
+            universe.set(x, y, ALIVE);
+        }
+        Board {
+            universe,
+            hashlife: None,
+            field: Field::new(size.width, size.height),
+            generation: 0,
+            redraw: HashSet::new(),
+            history: History::new(),
+            timeline: Timeline::new(TIMELINE_LENGTH),
+            start: None,
+            detector: CycleDetector::new(),
+        }
+    }
+
+    fn plain() -> Stepper {
+        Stepper { engine: Engine::Plain, step: 0 }
+    }
+
+    fn quiet() -> ActiveNoise {
+        ActiveNoise { noise: Noise::default(), seed: 1 }
+    }
+
+    fn cells(universe: &Universe) -> Vec<(i64, i64, CellState)> {
+        let mut cells: Vec<_> = universe.iter().collect();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn centre(layout: CellLayout, x: i64, y: i64) -> Vec2 {
+        Vec2::new((x as f32 + layout.row_shift(y as f32)) * CELL_SIZE, y as f32 * layout.row_height())
+    }
+
+    #[test]
+    fn hex_neighbours_are_one_cell_apart() {
+        let layout = CellLayout::Hex { middle: 50 };
+        let middle = centre(layout, 40, 60);
+        for (dx, dy) in [(1, 1), (1, 0), (0, -1), (-1, -1), (-1, 0), (0, 1)] {
+            let distance = centre(layout, 40 + dx, 60 + dy).distance(middle);
+            assert!((distance - CELL_SIZE).abs() < 1e-3, "({dx}, {dy}) is {distance} away");
+        }
+        for (dx, dy) in [(1, -1), (-1, 1)] {
+            assert!(centre(layout, 40 + dx, 60 + dy).distance(middle) > CELL_SIZE * 1.5);
+        }
+    }
+
+    #[test]
+    fn points_belong_to_the_nearest_cell() {
+        for layout in [CellLayout::Square, CellLayout::Hex { middle: 50 }] {
+            for i in 0..2000 {
+                // a spread of points over a patch of the board
+                let point = Vec2::new((i * 37 % 601) as f32 + 0.5, (i * 53 % 499) as f32 + 0.5) + Vec2::splat(500.0);
+                let (x, y) = layout.cell_at(point);
+                let distance = centre(layout, x, y).distance(point);
+                for (dx, dy) in [(1, 1), (1, 0), (0, -1), (-1, -1), (-1, 0), (0, 1), (1, -1), (-1, 1)] {
+                    let other = centre(layout, x + dx, y + dy).distance(point);
+                    assert!(distance <= other + 1e-3, "{point} is nearer ({}, {}) than ({x}, {y})", x + dx, y + dy);
                 }
             }
+        }
+    }
 
-            if cell.state != CellState::Dying {
-                if neighbour_cnt == 3 {
-                    cell.state = CellState::Alive;
-                    *sprite = sprite_images.alive_cell.clone();
+    #[test]
+    fn parses_board_sizes() {
+        assert_eq!("200".parse(), Ok(BoardSize { width: 200, height: 200 }));
+        assert_eq!("200x150".parse(), Ok(BoardSize { width: 200, height: 150 }));
+        assert_eq!("64, 32".parse(), Ok(BoardSize { width: 64, height: 32 }));
+        for bad in ["", "0", "-5", "10x", "5000x10", "ten"] {
+            assert!(bad.parse::<BoardSize>().is_err(), "{bad} parsed");
+        }
+    }
+
+    #[test]
+    fn rule_lines_carry_the_board_size() {
+        let line = RuleLine::parse("B3/S23:T200,150").unwrap();
+        assert_eq!(line.topology, Some(Topology::Torus));
+        assert_eq!(line.size, Some(BoardSize { width: 200, height: 150 }));
+        assert_eq!(RuleLine::parse("B3/S23:K40*,30").unwrap().size, Some(BoardSize { width: 40, height: 30 }));
+        assert_eq!(RuleLine::parse("B3/S23:T80").unwrap().size, Some(BoardSize { width: 80, height: 80 }));
+        assert_eq!(RuleLine::parse("B3/S23:T").unwrap().size, None);
+        assert_eq!(RuleLine::parse("B3/S23").unwrap().size, None);
+    }
+
+    #[test]
+    fn rule_line_reads_back() {
+        let size = BoardSize { width: 120, height: 90 };
+        for topology in [Topology::Plane, Topology::Torus, Topology::Klein, Topology::Mirror] {
+            let line = RuleLine::parse(&rule_line(&Rule::life().into(), &Noise::default(), topology, size)).unwrap();
+            assert_eq!(line.topology.unwrap_or(Topology::Plane), topology);
+            assert_eq!(line.size, topology.is_bounded().then_some(size));
+        }
+    }
+
+    #[test]
+    fn bands_go_round_the_threads() {
+        assert_eq!(band_height(100, 4), 25);
+        assert_eq!(band_height(100, 3), 34);
+        assert_eq!(band_height(100, 1), 100);
+        assert_eq!(band_height(100, 0), 100);
+        assert_eq!(band_height(100, 64), MIN_BAND_HEIGHT);
+        assert_eq!(band_height(5, 8), MIN_BAND_HEIGHT);
+    }
+
+    #[test]
+    fn bounded_boards_step_like_a_grid() {
+        let size = BoardSize { width: 70, height: 45 };
+        // a glider heading over the bottom edge, a blinker and some debris
+        let mut seed: Vec<_> = GLIDER.iter().map(|&(x, y)| (x + 10, y)).collect();
+        seed.extend([(30, 20), (31, 20), (32, 20), (50, 40), (51, 41), (52, 41), (52, 42), (60, 10), (61, 11)]);
+        for (rule, topology) in [("B3/S23", Topology::Torus), ("B3/S23", Topology::Klein), ("B036/S23", Topology::Dead)] {
+            let rule: Automaton = rule.parse().unwrap();
+            let mut board = board(&seed, size);
+            let mut grid = board.universe.window(0, 0, size.width, size.height).with_topology(topology);
+            for generation in 0..60 {
+                advance(&mut board, &rule, &quiet(), topology, size, &plain(), None);
+                grid.step(&rule);
+                let mut expected = Universe::new();
+                expected.paste(&grid, 0, 0);
+                assert_eq!(cells(&board.universe), cells(&expected), "{rule} on {topology:?} at {generation}");
+            }
+        }
+    }
+
+    #[test]
+    fn b0_rules_step_every_band() {
+        // nothing has changed, but B0 fills every empty cell of the board
+        let size = BoardSize { width: 20, height: 40 };
+        let rule: Automaton = "B0/S".parse().unwrap();
+        let mut board = board(&[], size);
+        advance(&mut board, &rule, &quiet(), Topology::Dead, size, &plain(), None);
+        assert_eq!(board.universe.population(), 800);
+    }
+
+    #[test]
+    fn only_changed_chunks_are_redrawn() {
+        let size = BoardSize::default();
+        let life = Rule::life().into();
+        let mut block = board(&[(0, 0), (1, 0), (0, 1), (1, 1)], size);
+        advance(&mut block, &life, &quiet(), Topology::Plane, size, &plain(), None);
+        block.redraw.clear();
+        advance(&mut block, &life, &quiet(), Topology::Plane, size, &plain(), None);
+        assert!(block.redraw.is_empty());
+
+        let mut blinker = board(&[(199, -100), (200, -100), (201, -100)], size);
+        advance(&mut blinker, &life, &quiet(), Topology::Plane, size, &plain(), None);
+        assert_eq!(blinker.redraw, HashSet::from([chunk_of(200, -100)]));
+    }
+
+    #[test]
+    fn cells_are_coloured_by_state_and_bounded_boards_end() {
+        let colours = [[0, 0, 0], [200, 10, 10], [10, 200, 10]];
+        let size = BoardSize { width: 10, height: 5 };
+        let mut board = board(&[(1, 1), (-30, 40)], size);
+        board.universe.set(2, 1, 2);
+        board.universe.set(3, 1, 9);
+        let life = Rule::life().into();
+        let colour = |topology, x, y| cell_colour(&board, &life, topology, size, &colours, x, y);
+        assert_eq!(colour(Topology::Torus, 1, 1), [200, 10, 10, 255]);
+        assert_eq!(colour(Topology::Torus, 2, 1), [10, 200, 10, 255]);
+        // states the rule has no colour for still show
+        assert_eq!(colour(Topology::Torus, 3, 1), [255; 4]);
+        assert_eq!(colour(Topology::Torus, 0, 0), EMPTY_COLOUR);
+        assert_eq!(colour(Topology::Torus, 10, 0), [0; 4]);
+        assert_eq!(colour(Topology::Torus, -30, 40), [0; 4]);
+        assert_eq!(colour(Topology::Plane, -30, 40), [200, 10, 10, 255]);
+    }
+
+    #[test]
+    fn field_values_run_along_the_ramp() {
+        let size = BoardSize { width: 4, height: 4 };
+        let mut board = board(&[], size);
+        board.field.set(1, 1, 1.0);
+        let lenia: Automaton = "Lenia".parse().unwrap();
+        let colour = |x, y| cell_colour(&board, &lenia, Topology::Plane, size, &[], x, y);
+        assert_eq!(colour(0, 0), ramp_colour(0.0));
+        assert_eq!(colour(1, 1), ramp_colour(1.0));
+        assert_eq!(colour(4, 1), [0; 4]);
+        let brightness = |[r, g, b, _]: [u8; 4]| r as u32 + g as u32 + b as u32;
+        assert!((0..=10).map(|i| brightness(ramp_colour(i as f32 / 10.0))).is_sorted());
+        assert_eq!(ramp_colour(-1.0), ramp_colour(0.0));
+        assert_eq!(ramp_colour(2.0), ramp_colour(1.0));
+    }
+
+    #[test]
+    fn blocks_are_shaded_by_how_full_they_are() {
+        let view = View { u0: 0, y0: 0, width: 3, height: 2, level: 2 };
+        let colours = [[0, 0, 0], [250, 250, 250]];
+        let mut cells: Vec<_> = (0..4).flat_map(|x| (0..4).map(move |y| (x, y))).collect();
+        cells.extend([(4, 0), (5, 1), (6, 2), (7, 3)]);
+        let life = Rule::life().into();
+        let mut board = board(&cells, BoardSize::default());
+        let draw = |board: &Board, topology, size| {
+            let mut data = vec![0; 3 * 2 * 4];
+            draw_blocks(view, board, &life, topology, size, CellLayout::Square, &colours, &mut data);
+            // the bottom row of blocks is the image's last
+            data[12..].chunks_exact(4).map(|texel| <[u8; 4]>::try_from(texel).unwrap()).collect::<Vec<_>>()
+        };
+
+        let drawn = draw(&board, Topology::Plane, BoardSize::default());
+        assert_eq!(drawn[0], [250, 250, 250, 255]);
+        // a quarter full is shaded half way, by the square root
+        let live = [250.0, 250.0, 250.0, 255.0];
+        let half: [u8; 4] = std::array::from_fn(|c| (EMPTY_COLOUR[c] as f32 + (live[c] - EMPTY_COLOUR[c] as f32) * 0.5) as u8);
+        assert_eq!(drawn[1], half);
+        assert_eq!(drawn[2], EMPTY_COLOUR);
+
+        // beyond a bounded board's edge blocks are left clear
+        let narrow = BoardSize { width: 6, height: 8 };
+        assert_eq!(draw(&board, Topology::Torus, narrow)[2], [0; 4]);
+
+        // HashLife's tree is counted the same way
+        board.hashlife = Some(HashLife::from_universe(life.clone(), &board.universe));
+        assert_eq!(draw(&board, Topology::Plane, BoardSize::default()), drawn);
+    }
+
+    /// An app running just the stepper over a glider, paused, with a
+    /// stepper of `engine` jumping `2^step` generations.
+    fn stepping_app(engine: Engine, step: u8) -> App {
+        let mut app = App::new();
+        app.add_event::<StepOnceEvent>()
+            .add_event::<RunToEvent>()
+            .add_event::<TakeCensusEvent>()
+            .insert_resource(board(&GLIDER, BoardSize::default()))
+            .insert_resource(ActiveRule(Rule::life().into()))
+            .insert_resource(quiet())
+            .insert_resource(ActiveTopology(Topology::Plane))
+            .insert_resource(BoardSize::default())
+            .insert_resource(Stepper { engine, step })
+            .insert_resource(IsSimulationRunning(false))
+            .insert_resource(Speed { steps_per_second: MAX_SPEED, target: None, owed: 0.0 })
+            .insert_resource(Time::default())
+            .insert_resource(AutoPause(true))
+            .add_system(simulation_step);
+        app
+    }
+
+    fn generation(app: &App) -> u64 {
+        app.world.resource::<Board>().generation
+    }
+
+    #[test]
+    fn steps_once_while_paused() {
+        let mut app = stepping_app(Engine::Plain, 0);
+        app.update();
+        assert_eq!(generation(&app), 0);
+        app.world.send_event(StepOnceEvent);
+        app.update();
+        assert_eq!(generation(&app), 1);
+        assert!(!app.world.resource::<IsSimulationRunning>().0);
+    }
+
+    #[test]
+    fn runs_to_the_target_and_pauses() {
+        for (engine, step) in [(Engine::Plain, 0), (Engine::HashLife, 6)] {
+            let mut app = stepping_app(engine, step);
+            app.world.send_event(RunToEvent(100));
+            // steps past the frame budget wait for the next frame
+            for _ in 0..1000 {
+                app.update();
+                if !app.world.resource::<IsSimulationRunning>().0 {
+                    break;
                 }
             }
+            // HashLife jumps no further than the target
+            assert_eq!(generation(&app), 100);
+            assert_eq!(app.world.resource::<Speed>().target, None);
+            let board = app.world.resource_mut::<Board>().into_inner();
+            board.settle();
+            let mut glider: Vec<_> = GLIDER.iter().map(|&(x, y)| (x + 25, y - 25, ALIVE)).collect();
+            glider.sort_unstable();
+            assert_eq!(cells(&board.universe), glider);
+        }
+    }
+
+    #[test]
+    fn running_to_a_past_generation_does_nothing() {
+        let mut app = stepping_app(Engine::Plain, 0);
+        app.world.resource_mut::<Board>().generation = 50;
+        app.world.send_event(RunToEvent(20));
+        app.update();
+        assert_eq!(generation(&app), 50);
+        assert!(!app.world.resource::<IsSimulationRunning>().0);
+    }
+
+    #[test]
+    fn reset_puts_back_the_board_from_start() {
+        let size = BoardSize::default();
+        let life = Rule::life().into();
+        let mut board = board(&GLIDER, size);
+        let started = cells(&board.universe);
+        // nothing to go back to until Start is pressed
+        board.reset();
+        assert_eq!(board.generation, 0);
+
+        board.generation = 7;
+        board.start = Some((7, board.frame(false)));
+        for _ in 0..9 {
+            advance(&mut board, &life, &quiet(), Topology::Plane, size, &plain(), None);
+        }
+        let ran = cells(&board.universe);
+        board.reset();
+        assert_eq!((board.generation, cells(&board.universe)), (7, started.clone()));
+        assert!(board.timeline.is_empty());
+
+        // the reset is an edit, so it can be taken back
+        board.undo();
+        assert_eq!(cells(&board.universe), ran);
+        board.redo();
+        assert_eq!(cells(&board.universe), started);
+    }
+
+    #[test]
+    fn clear_empties_the_board_and_the_field() {
+        let size = BoardSize { width: 8, height: 6 };
+        let mut board = board(&GLIDER, size);
+        board.field.set(2, 3, 0.5);
+        board.generation = 40;
+        board.record(false);
+        board.clear();
+        assert_eq!(board.universe.population(), 0);
+        assert_eq!(board.field.get(2, 3), 0.0);
+        assert_eq!((board.field.width(), board.field.height()), (8, 6));
+        assert_eq!(board.generation, 0);
+        assert!(board.timeline.is_empty());
+
+        board.undo();
+        assert_eq!(board.universe.population(), 5);
+        assert_eq!(board.field.get(2, 3), 0.5);
+    }
+
+    #[test]
+    fn b0_rules_are_stepped_without_hashlife() {
+        let b0: Automaton = "B0/S".parse().unwrap();
+        assert!(!Stepper::can_hash(&b0, &Noise::default(), Topology::Plane));
+        assert!(Stepper::can_hash(&Rule::life().into(), &Noise::default(), Topology::Plane));
+
+        let mut app = stepping_app(Engine::HashLife, 4);
+        app.insert_resource(ActiveRule(b0));
+        app.world.send_event(StepOnceEvent);
+        app.update();
+        let board = app.world.resource::<Board>();
+        assert!(board.hashlife.is_none());
+        assert_eq!(board.generation, 1);
+    }
+
+    #[test]
+    fn hashlife_runs_pause_on_their_fate() {
+        let mut app = stepping_app(Engine::HashLife, 4);
+        app.insert_resource(board(&[(-1, 0), (0, 0), (1, 0)], BoardSize::default()));
+        app.world.send_event(RunToEvent(10_000));
+        for _ in 0..100 {
+            app.update();
+            if !app.world.resource::<IsSimulationRunning>().0 {
+                break;
+            }
         }
+        let board = app.world.resource::<Board>();
+        assert_eq!(board.generation, 16);
+        assert_eq!(board.fate(), Some(Fate::Oscillating { generation: 0, period: 2 }));
+        // and each jump can be rewound to
+        assert_eq!(board.timeline.len(), 2);
+        assert_eq!(board.timeline.generation(1), Some(16));
     }
-}
\ No newline at end of file
+}
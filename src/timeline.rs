@@ -0,0 +1,182 @@
+//! A record of past generations to rewind through. Boards are kept as
+//! run-length snapshots in a ring buffer, so the oldest drop off as new ones
+//! come in.
+
+use std::collections::VecDeque;
+use crate::grid::CellState;
+use crate::universe::Universe;
+
+/// A universe's cells packed into runs of equal state along each row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Run {
+    x: i64,
+    y: i64,
+    length: u32,
+    state: CellState,
+}
+
+impl Snapshot {
+    pub fn of(universe: &Universe) -> Self {
+        let mut cells: Vec<_> = universe.iter().collect();
+        cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        let mut runs: Vec<Run> = vec![];
+        for (x, y, state) in cells {
+            match runs.last_mut() {
+                Some(run) if run.y == y && run.x + run.length as i64 == x && run.state == state => run.length += 1,
+                _ => runs.push(Run { x, y, length: 1, state }),
+            }
+        }
+        Self { runs }
+    }
+
+    pub fn to_universe(&self) -> Universe {
+        let mut universe = Universe::new();
+        for run in &self.runs {
+            for x in run.x..run.x + run.length as i64 {
+                universe.set(x, run.y, run.state);
+            }
+        }
+        universe
+    }
+}
+
+/// The last few recorded frames, each with its generation, and which of them
+/// the board was last rewound to.
+#[derive(Debug, Clone)]
+pub struct Timeline<T> {
+    frames: VecDeque<(u64, T)>,
+    capacity: usize,
+    position: Option<usize>,
+}
+
+impl<T> Timeline<T> {
+    /// An empty timeline keeping up to `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            position: None,
+        }
+    }
+
+    /// Adds a frame after the newest, forgetting the oldest if the timeline
+    /// is full. A frame of the same generation as the newest replaces it. If
+    /// the board was rewound, this forks: the frame rewound to and everything
+    /// after it are dropped first.
+    pub fn record(&mut self, generation: u64, frame: T) {
+        match self.position.take() {
+            Some(i) => self.frames.truncate(i),
+            None if self.frames.back().is_some_and(|&(newest, _)| newest == generation) => {
+                self.frames.pop_back();
+            }
+            None => {}
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((generation, frame));
+    }
+
+    /// Rewinds to frame `i`, oldest first, returning its generation and
+    /// frame, or `None` if there is no such frame.
+    pub fn seek(&mut self, i: usize) -> Option<(u64, &T)> {
+        let (generation, frame) = self.frames.get(i)?;
+        self.position = Some(i);
+        Some((*generation, frame))
+    }
+
+    /// The frame last rewound to, or `None` if the board has not been rewound
+    /// since the last frame was recorded.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Generation of frame `i`.
+    pub fn generation(&self, i: usize) -> Option<u64> {
+        self.frames.get(i).map(|&(generation, _)| generation)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.position = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::ALIVE;
+
+    #[test]
+    fn snapshots_give_back_the_universe() {
+        let mut universe = Universe::new();
+        for x in -70..70 {
+            universe.set(x, 3, ALIVE);
+        }
+        universe.set(5, -200, 2);
+        universe.set(6, -200, 2);
+        universe.set(7, -200, ALIVE);
+        universe.set(1000, 1000, ALIVE);
+        let snapshot = Snapshot::of(&universe);
+        assert_eq!(snapshot.runs.len(), 4);
+        let mut cells: Vec<_> = snapshot.to_universe().iter().collect();
+        let mut expected: Vec<_> = universe.iter().collect();
+        cells.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(cells, expected);
+        assert_eq!(Snapshot::of(&Universe::new()), Snapshot::default());
+    }
+
+    #[test]
+    fn forgets_the_oldest_frames() {
+        let mut timeline = Timeline::new(3);
+        for generation in 0..5 {
+            timeline.record(generation, generation * 10);
+        }
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline.generation(0), Some(2));
+        assert_eq!(timeline.seek(2), Some((4, &40)));
+        assert_eq!(timeline.seek(3), None);
+    }
+
+    #[test]
+    fn the_same_generation_replaces_the_newest() {
+        let mut timeline = Timeline::new(4);
+        timeline.record(0, 'a');
+        timeline.record(1, 'b');
+        timeline.record(1, 'c');
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline.seek(1), Some((1, &'c')));
+    }
+
+    #[test]
+    fn recording_after_a_rewind_forks() {
+        let mut timeline = Timeline::new(8);
+        for generation in 0..5 {
+            timeline.record(generation, generation);
+        }
+        assert_eq!(timeline.seek(2), Some((2, &2)));
+        assert_eq!(timeline.position(), Some(2));
+        timeline.record(2, 20);
+        assert_eq!(timeline.position(), None);
+        assert_eq!(timeline.len(), 3);
+        assert_eq!(timeline.seek(2), Some((2, &20)));
+
+        timeline.clear();
+        assert!(timeline.is_empty());
+        assert_eq!(timeline.position(), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! What lies beyond the edges of a bounded board.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// No edges at all: the board is an unbounded
+    /// [`Universe`](crate::universe::Universe). A bounded grid treats it as
+    /// dead edges.
+    Plane,
+    /// Everything outside the board is empty.
+    #[default]
+    Dead,
+    /// Opposite edges are joined: leave on the right, come back on the left.
+    Torus,
+    /// Left and right edges are joined as on a torus; top and bottom are
+    /// joined with a twist, so crossing them mirrors the column.
+    Klein,
+    /// The projective plane: both pairs of edges are joined with a twist.
+    CrossSurface,
+    /// Each edge reflects the board, as if a mirror stood along it.
+    Mirror,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 6] = [
+        Topology::Plane,
+        Topology::Dead,
+        Topology::Torus,
+        Topology::Klein,
+        Topology::CrossSurface,
+        Topology::Mirror,
+    ];
+
+    /// The board cell that `(x, y)` stands for on a `width` x `height` board,
+    /// or `None` if it lies beyond a dead edge.
+    pub fn resolve(self, x: i32, y: i32, width: i32, height: i32) -> Option<(i32, i32)> {
+        let inside = x >= 0 && x < width && y >= 0 && y < height;
+        if inside {
+            return Some((x, y));
+        }
+        let crossed_x = x.div_euclid(width) % 2 != 0;
+        let crossed_y = y.div_euclid(height) % 2 != 0;
+        let (wx, wy) = (x.rem_euclid(width), y.rem_euclid(height));
+        match self {
+            Topology::Plane | Topology::Dead => None,
+            Topology::Torus => Some((wx, wy)),
+            Topology::Klein => Some((if crossed_y { width - 1 - wx } else { wx }, wy)),
+            Topology::CrossSurface => Some((
+                if crossed_y { width - 1 - wx } else { wx },
+                if crossed_x { height - 1 - wy } else { wy },
+            )),
+            Topology::Mirror => {
+                let reflect = |v: i32, size: i32| {
+                    let v = v.rem_euclid(2 * size);
+                    if v < size { v } else { 2 * size - 1 - v }
+                };
+                Some((reflect(x, width), reflect(y, height)))
+            }
+        }
+    }
+
+    /// Whether the board has edges at all.
+    pub fn is_bounded(self) -> bool {
+        self != Topology::Plane
+    }
+
+    /// The next topology in [`Topology::ALL`], wrapping round.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&t| t == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Plane => "Infinite plane",
+            Topology::Dead => "Dead edges",
+            Topology::Torus => "Torus",
+            Topology::Klein => "Klein bottle",
+            Topology::CrossSurface => "Cross-surface",
+            Topology::Mirror => "Mirror",
+        }
+    }
+
+    /// Golly's bounded grid suffix for a `width` x `height` board, such as
+    /// `T100,100`, or nothing for the unbounded plane. Golly has no mirrored
+    /// edges, so those are written `M`.
+    pub fn spec(self, width: i32, height: i32) -> String {
+        match self {
+            Topology::Plane => String::new(),
+            Topology::Klein => format!("K{width},{height}*"),
+            _ => format!("{self}{width},{height}"),
+        }
+    }
+}
+
+/// Written as the letter that starts its [`Topology::spec`]; the plane has
+/// none.
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Topology::Plane => return Ok(()),
+            Topology::Dead => 'P',
+            Topology::Torus => 'T',
+            Topology::Klein => 'K',
+            Topology::CrossSurface => 'C',
+            Topology::Mirror => 'M',
+        };
+        write!(f, "{letter}")
+    }
+}
+
+impl FromStr for Topology {
+    type Err = TopologyParseError;
+
+    /// Accepts a Golly bounded grid suffix such as `T100,100`, `K100,100*` or
+    /// `C100,100`, or just its letter. Any size is left for the board to read.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let topology = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('P') => Topology::Dead,
+            Some('T') => Topology::Torus,
+            Some('K') => Topology::Klein,
+            Some('C') => Topology::CrossSurface,
+            Some('M') => Topology::Mirror,
+            _ => return Err(TopologyParseError(s.to_string())),
+        };
+        let size_ok = chars.as_str().split(',').all(|n| {
+            let n = n.trim().trim_end_matches('*');
+            n.is_empty() || n.parse::<u32>().is_ok()
+        });
+        if size_ok {
+            Ok(topology)
+        } else {
+            Err(TopologyParseError(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologyParseError(pub String);
+
+impl fmt::Display for TopologyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a topology, expected P, T, K, C or M, optionally with a size such as T100,100", self.0)
+    }
+}
+
+impl std::error::Error for TopologyParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::rule::Rule;
+
+    #[test]
+    fn edges_lead_where_they_should() {
+        let resolve = |topology: Topology, x, y| topology.resolve(x, y, 5, 4);
+        for topology in Topology::ALL {
+            assert_eq!(resolve(topology, 2, 3), Some((2, 3)));
+        }
+        assert_eq!(resolve(Topology::Dead, 5, 0), None);
+        assert_eq!(resolve(Topology::Plane, -1, 0), None);
+        assert_eq!(resolve(Topology::Torus, 5, -1), Some((0, 3)));
+        assert_eq!(resolve(Topology::Klein, 5, 1), Some((0, 1)));
+        assert_eq!(resolve(Topology::Klein, 1, 4), Some((3, 0)));
+        assert_eq!(resolve(Topology::Klein, 1, -1), Some((3, 3)));
+        assert_eq!(resolve(Topology::CrossSurface, 5, 1), Some((0, 2)));
+        assert_eq!(resolve(Topology::CrossSurface, 1, 4), Some((3, 0)));
+        assert_eq!(resolve(Topology::Mirror, -1, 4), Some((0, 3)));
+        assert_eq!(resolve(Topology::Mirror, 6, -2), Some((3, 1)));
+    }
+
+    #[test]
+    fn a_glider_comes_home_round_a_torus() {
+        let mut start = Grid::new(8, 8).with_topology(Topology::Torus);
+        for (x, y) in [(1, 7), (2, 6), (0, 5), (1, 5), (2, 5)] {
+            start.set(x, y, 1);
+        }
+        let mut grid = start.clone();
+        for generation in 1..=32 {
+            grid.step(&Rule::life());
+            assert_eq!(grid.population(), 5);
+            assert_eq!(grid == start, generation == 32);
+        }
+    }
+
+    #[test]
+    fn a_glider_crossing_a_twisted_edge_comes_back_mirrored() {
+        let mut grid = Grid::new(10, 10).with_topology(Topology::Klein);
+        for (x, y) in [(1, 3), (2, 2), (0, 1), (1, 1), (2, 1)] {
+            grid.set(x, y, 1);
+        }
+        // twelve generations take it three cells right and down, on the plane
+        // partly below the bottom edge
+        for _ in 0..12 {
+            grid.step(&Rule::life());
+        }
+        let mut expected = Grid::new(10, 10).with_topology(Topology::Klein);
+        for (x, y) in [(4, 0), (5, -1), (3, -2), (4, -2), (5, -2)] {
+            let (x, y) = Topology::Klein.resolve(x, y, 10, 10).unwrap();
+            expected.set(x, y, 1);
+        }
+        assert!(expected.is_alive(4, 9) && expected.is_alive(6, 8));
+        assert_eq!(grid.population(), 5);
+        assert_eq!(grid, expected);
+    }
+
+    #[test]
+    fn parses_and_prints_golly_suffixes() {
+        for topology in Topology::ALL.into_iter().filter(|t| t.is_bounded()) {
+            assert_eq!(topology.spec(100, 80).parse::<Topology>(), Ok(topology));
+            assert_eq!(topology.to_string().parse::<Topology>(), Ok(topology));
+        }
+        assert_eq!(Topology::Torus.spec(100, 80), "T100,80");
+        assert_eq!(Topology::Klein.spec(100, 80), "K100,80*");
+        assert_eq!(Topology::Plane.spec(100, 80), "");
+        assert_eq!("t30,30".parse::<Topology>(), Ok(Topology::Torus));
+        assert!("Q".parse::<Topology>().is_err());
+        assert!("T30,x".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn next_cycles_through_every_topology() {
+        let mut topology = Topology::Plane;
+        let mut seen = vec![];
+        for _ in 0..Topology::ALL.len() {
+            seen.push(topology);
+            topology = topology.next();
+        }
+        assert_eq!(topology, Topology::Plane);
+        assert_eq!(seen, Topology::ALL);
+    }
+}
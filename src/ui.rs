@@ -1,10 +1,19 @@
 use bevy::diagnostic::{Diagnostic, Diagnostics, FrameTimeDiagnosticsPlugin};
 use bevy::prelude::*;
 use crate::input::MainCamera;
+use game_of_life::fate::Fate;
+use crate::simulation::{
+    rule_line, ActiveNoise, ActiveRule, ActiveTopology, AutoPause, Board, BoardSize, ObjectCensus, DrawState, Engine, RuleLine, Speed, Stepper, MAX_SPEED,
+    MIN_SPEED,
+};
 
 const BUTTON_ACTIVE: Color = Color::rgb(0.8, 0.8, 0.8);
 const BUTTON_HOVER: Color = Color::rgb(0.4, 0.8, 0.8);
 const BUTTON_DOWN: Color = Color::rgb(0.4, 1.0, 1.0);
+const RULE_EDITING: Color = Color::rgb(0.4, 1.0, 0.4);
+const SLIDER_WIDTH: f32 = 200.0;
+/// Rows of the census shown; the rest are only exported.
+const CENSUS_ROWS: usize = 16;
 
 pub struct GameExitEvent;
 
@@ -14,14 +23,95 @@ pub struct StopSimulationEvent;
 
 pub struct ResetSimulationEvent;
 
+pub struct ClearBoardEvent;
+
 pub struct MarkCellForDeathEvent;
 
+pub struct ChangeRuleEvent(pub RuleLine);
+
+pub struct SaveSessionEvent;
+
+pub struct LoadSessionEvent;
+
+pub struct CycleTopologyEvent;
+
+pub struct ToggleHashLifeEvent;
+
+/// Advance one step, running or not.
+pub struct StepOnceEvent;
+
+/// Run flat out until this generation, then pause.
+pub struct RunToEvent(pub u64);
+
+/// Count the objects on the board.
+pub struct TakeCensusEvent;
+
+/// Write the last census to a file.
+pub struct ExportCensusEvent;
+
+/// Go back one generation through the timeline.
+pub struct StepBackEvent;
+
+/// Go back to this frame of the timeline, oldest first.
+pub struct RewindEvent(pub usize);
+
+/// Rulestring or rule file name being typed in after pressing the Rule button, or the generation
+/// to run to after pressing Run to. While editing, keystrokes go to the buffer instead of the camera.
+#[derive(Resource, Default)]
+pub struct RuleInput {
+    editing: bool,
+    prompt: Prompt,
+    buffer: String,
+    error: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Default)]
+enum Prompt {
+    #[default]
+    Rule,
+    RunTo,
+}
+
+impl RuleInput {
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+}
+
 #[derive(Component)]
 struct FpsText;
 
 #[derive(Component)]
 struct StatsText;
 
+#[derive(Component)]
+struct RuleText;
+
+#[derive(Component)]
+struct SpeedText;
+
+/// Bar setting the speed, on a log scale from [`MIN_SPEED`] at the left to [`MAX_SPEED`].
+#[derive(Component)]
+struct SpeedSlider;
+
+/// The filled part of the [`SpeedSlider`].
+#[derive(Component)]
+struct SpeedFill;
+
+#[derive(Component)]
+struct CensusText;
+
+#[derive(Component)]
+struct TimelineText;
+
+/// Bar picking a frame of the timeline, the oldest at the left.
+#[derive(Component)]
+struct TimelineSlider;
+
+/// The filled part of the [`TimelineSlider`], up to the frame shown.
+#[derive(Component)]
+struct TimelineFill;
+
 #[derive(Component)]
 pub struct ClassicButton(ButtonType);
 
@@ -31,6 +121,18 @@ enum ButtonType {
     Stop,
     Exit,
     Reset,
+    Clear,
+    Rule,
+    Save,
+    Load,
+    Edges,
+    HashLife,
+    Step,
+    RunTo,
+    Back,
+    AutoPause,
+    Census,
+    Export,
 }
 
 pub struct MainMenuPlugin;
@@ -42,8 +144,28 @@ impl Plugin for MainMenuPlugin {
             .add_event::<StartSimulationEvent>()
             .add_event::<StopSimulationEvent>()
             .add_event::<ResetSimulationEvent>()
+            .add_event::<ClearBoardEvent>()
+            .add_event::<ChangeRuleEvent>()
+            .add_event::<SaveSessionEvent>()
+            .add_event::<LoadSessionEvent>()
+            .add_event::<CycleTopologyEvent>()
+            .add_event::<ToggleHashLifeEvent>()
+            .add_event::<StepOnceEvent>()
+            .add_event::<RunToEvent>()
+            .add_event::<TakeCensusEvent>()
+            .add_event::<ExportCensusEvent>()
+            .add_event::<StepBackEvent>()
+            .add_event::<RewindEvent>()
+            .init_resource::<RuleInput>()
             .add_startup_system(setup)
             .add_system(button_system)
+            .add_system(rule_input_system)
+            .add_system(rule_text_update_system)
+            .add_system(speed_slider_system)
+            .add_system(speed_text_update_system)
+            .add_system(timeline_slider_system)
+            .add_system(timeline_text_update_system)
+            .add_system(census_text_update_system)
             .add_system(fps_update_system)
             .add_system(stats_update_system);
     }
@@ -67,7 +189,12 @@ fn setup(
                 font: asset_server.load("fonts/minecraft_font.ttf"),
                 font_size: 15.0,
                 color: Color::GOLD,
-            })
+            }),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: RULE_EDITING,
+            }),
         ])
             .with_style(Style {
                 position_type: PositionType::Absolute,
@@ -105,6 +232,19 @@ fn setup(
                     color: Color::ANTIQUE_WHITE,
                 },
             ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::new(
+                " Generation: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
             TextSection::from_style(TextStyle {
                 font: asset_server.load("fonts/minecraft_font.ttf"),
                 font_size: 15.0,
@@ -124,6 +264,302 @@ fn setup(
         StatsText
     ));
 
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Rule: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::new(
+                " Seed: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::new(
+                " Draw: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::new(
+                " Edges: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::new(
+                " Engine: ",
+                TextStyle {
+                    font: asset_server.load("fonts/minecraft_font.ttf"),
+                    font_size: 15.0,
+                    color: Color::ANTIQUE_WHITE,
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::GOLD,
+            }),
+            TextSection::from_style(TextStyle {
+                font: asset_server.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Color::ORANGE_RED,
+            })
+        ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(45.0),
+                    left: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            })
+        ,
+        RuleText
+    ));
+
+    commands.spawn(
+        NodeBundle { // Speed controls
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(5.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(build_small_button())
+                .with_children(|parent| {
+                    parent.spawn(build_small_text("Halt", &asset_server));
+                })
+                .insert(ClassicButton(ButtonType::AutoPause));
+
+            parent
+                .spawn(build_small_button())
+                .with_children(|parent| {
+                    parent.spawn(build_small_text("Step", &asset_server));
+                })
+                .insert(ClassicButton(ButtonType::Step));
+
+            parent
+                .spawn(build_small_button())
+                .with_children(|parent| {
+                    parent.spawn(build_small_text("Run to", &asset_server));
+                })
+                .insert(ClassicButton(ButtonType::RunTo));
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(SLIDER_WIDTH), Val::Px(14.0)),
+                            margin: UiRect::horizontal(Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                        ..default()
+                    },
+                    SpeedSlider,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: Color::GOLD.into(),
+                            ..default()
+                        },
+                        SpeedFill,
+                    ));
+                });
+
+            parent.spawn((
+                TextBundle::from_sections([
+                    TextSection::new(
+                        "Speed: ",
+                        TextStyle {
+                            font: asset_server.load("fonts/minecraft_font.ttf"),
+                            font_size: 15.0,
+                            color: Color::ANTIQUE_WHITE,
+                        },
+                    ),
+                    TextSection::from_style(TextStyle {
+                        font: asset_server.load("fonts/minecraft_font.ttf"),
+                        font_size: 15.0,
+                        color: Color::GOLD,
+                    }),
+                    TextSection::from_style(TextStyle {
+                        font: asset_server.load("fonts/minecraft_font.ttf"),
+                        font_size: 15.0,
+                        color: RULE_EDITING,
+                    }),
+                ]),
+                SpeedText,
+            ));
+        });
+
+    commands.spawn(
+        NodeBundle { // Census
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(85.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    background_color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(build_small_button())
+                        .with_children(|parent| {
+                            parent.spawn(build_small_text("Census", &asset_server));
+                        })
+                        .insert(ClassicButton(ButtonType::Census));
+
+                    parent
+                        .spawn(build_small_button())
+                        .with_children(|parent| {
+                            parent.spawn(build_small_text("Export", &asset_server));
+                        })
+                        .insert(ClassicButton(ButtonType::Export));
+                });
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/minecraft_font.ttf"),
+                        font_size: 15.0,
+                        color: Color::ANTIQUE_WHITE,
+                    },
+                ),
+                CensusText,
+            ));
+        });
+
+    commands.spawn(
+        NodeBundle { // Timeline controls
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(32.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(build_small_button())
+                .with_children(|parent| {
+                    parent.spawn(build_small_text("Back", &asset_server));
+                })
+                .insert(ClassicButton(ButtonType::Back));
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(SLIDER_WIDTH), Val::Px(14.0)),
+                            margin: UiRect::horizontal(Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                        ..default()
+                    },
+                    TimelineSlider,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: Color::ORANGE_RED.into(),
+                            ..default()
+                        },
+                        TimelineFill,
+                    ));
+                });
+
+            parent.spawn((
+                TextBundle::from_sections([
+                    TextSection::new(
+                        "Timeline: ",
+                        TextStyle {
+                            font: asset_server.load("fonts/minecraft_font.ttf"),
+                            font_size: 15.0,
+                            color: Color::ANTIQUE_WHITE,
+                        },
+                    ),
+                    TextSection::from_style(TextStyle {
+                        font: asset_server.load("fonts/minecraft_font.ttf"),
+                        font_size: 15.0,
+                        color: Color::ORANGE_RED,
+                    }),
+                ]),
+                TimelineText,
+            ));
+        });
+
     commands.spawn(
         NodeBundle { // Root
             style: Style {
@@ -182,6 +618,48 @@ fn setup(
                                 })
                                 .insert(ClassicButton(ButtonType::Reset));
 
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Clear", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::Clear));
+
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Rule", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::Rule));
+
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Save", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::Save));
+
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Load", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::Load));
+
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Edges", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::Edges));
+
+                            parent
+                                .spawn(build_button(&asset_server))
+                                .with_children(|parent| {
+                                    parent.spawn(build_text("Hash", &asset_server));
+                                })
+                                .insert(ClassicButton(ButtonType::HashLife));
+
                             parent
                                 .spawn(build_button(&asset_server))
                                 .with_children(|parent| {
@@ -196,7 +674,7 @@ fn setup(
 fn build_button(asset_server: &Res<AssetServer>) -> ButtonBundle {
     ButtonBundle {
         style: Style {
-            size: Size::new(Val::Px(150.0), Val::Px(50.0)),
+            size: Size::new(Val::Px(95.0), Val::Px(50.0)),
             margin: UiRect::all(Val::Auto),
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
@@ -206,6 +684,33 @@ fn build_button(asset_server: &Res<AssetServer>) -> ButtonBundle {
     }
 }
 
+fn build_small_button() -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(70.0), Val::Px(22.0)),
+            margin: UiRect::horizontal(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn build_small_text(value: &str, assets: &Res<AssetServer>) -> TextBundle {
+    TextBundle {
+        text: Text::from_section(
+            value,
+            TextStyle {
+                font: assets.load("fonts/minecraft_font.ttf"),
+                font_size: 15.0,
+                color: Default::default(),
+            },
+        ),
+        ..Default::default()
+    }
+}
+
 fn build_text(value: &str, assets: &Res<AssetServer>) -> TextBundle {
     TextBundle {
         text: Text::from_section(
@@ -220,12 +725,29 @@ fn build_text(value: &str, assets: &Res<AssetServer>) -> TextBundle {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn button_system(
     mut query: Query<(&Interaction, &mut BackgroundColor, &ClassicButton), (Changed<Interaction>, With<Button>)>,
     mut start_writer: EventWriter<StartSimulationEvent>,
     mut stop_writer: EventWriter<StopSimulationEvent>,
     mut exit_writer: EventWriter<GameExitEvent>,
     mut reset_writer: EventWriter<ResetSimulationEvent>,
+    mut clear_writer: EventWriter<ClearBoardEvent>,
+    mut save_writer: EventWriter<SaveSessionEvent>,
+    mut load_writer: EventWriter<LoadSessionEvent>,
+    mut topology_writer: EventWriter<CycleTopologyEvent>,
+    mut hashlife_writer: EventWriter<ToggleHashLifeEvent>,
+    mut step_writer: EventWriter<StepOnceEvent>,
+    mut back_writer: EventWriter<StepBackEvent>,
+    mut rule_input: ResMut<RuleInput>,
+    mut auto_pause: ResMut<AutoPause>,
+    (mut census_writer, mut export_writer): (EventWriter<TakeCensusEvent>, EventWriter<ExportCensusEvent>),
+    (active_rule, active_noise, active_topology, board_size): (
+        Res<ActiveRule>,
+        Res<ActiveNoise>,
+        Res<ActiveTopology>,
+        Res<BoardSize>,
+    ),
 ) {
     for (i, mut bc, cb) in query.iter_mut() {
         match *i {
@@ -244,18 +766,279 @@ fn button_system(
                     ButtonType::Reset => {
                         reset_writer.send(ResetSimulationEvent);
                     }
+                    ButtonType::Clear => {
+                        clear_writer.send(ClearBoardEvent);
+                    }
+                    ButtonType::Rule => {
+                        rule_input.editing = true;
+                        rule_input.prompt = Prompt::Rule;
+                        rule_input.buffer =
+                            rule_line(&active_rule.0, &active_noise.noise, active_topology.0, *board_size);
+                        rule_input.error = None;
+                    }
+                    ButtonType::Save => {
+                        save_writer.send(SaveSessionEvent);
+                    }
+                    ButtonType::Load => {
+                        load_writer.send(LoadSessionEvent);
+                    }
+                    ButtonType::Edges => {
+                        topology_writer.send(CycleTopologyEvent);
+                    }
+                    ButtonType::HashLife => {
+                        hashlife_writer.send(ToggleHashLifeEvent);
+                    }
+                    ButtonType::Step => {
+                        step_writer.send(StepOnceEvent);
+                    }
+                    ButtonType::Back => {
+                        back_writer.send(StepBackEvent);
+                    }
+                    ButtonType::AutoPause => {
+                        auto_pause.0 = !auto_pause.0;
+                    }
+                    ButtonType::Census => {
+                        census_writer.send(TakeCensusEvent);
+                    }
+                    ButtonType::Export => {
+                        export_writer.send(ExportCensusEvent);
+                    }
+                    ButtonType::RunTo => {
+                        rule_input.editing = true;
+                        rule_input.prompt = Prompt::RunTo;
+                        rule_input.buffer.clear();
+                        rule_input.error = None;
+                    }
                 }
             }
             Interaction::Hovered => {
                 *bc = BUTTON_HOVER.into();
             }
             Interaction::None => {
-                *bc = BUTTON_ACTIVE.into();
+                *bc = match cb.0 {
+                    ButtonType::Rule if rule_input.editing && rule_input.prompt == Prompt::Rule => RULE_EDITING.into(),
+                    ButtonType::RunTo if rule_input.editing && rule_input.prompt == Prompt::RunTo => RULE_EDITING.into(),
+                    ButtonType::AutoPause if auto_pause.0 => RULE_EDITING.into(),
+                    _ => BUTTON_ACTIVE.into(),
+                };
             }
         }
     }
 }
 
+fn rule_input_system(
+    mut chars: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut rule_input: ResMut<RuleInput>,
+    mut rule_writer: EventWriter<ChangeRuleEvent>,
+    mut run_to_writer: EventWriter<RunToEvent>,
+) {
+    if !rule_input.editing {
+        chars.clear();
+        return;
+    }
+
+    for c in chars.iter() {
+        if c.char.is_ascii_graphic() {
+            rule_input.buffer.push(c.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        rule_input.buffer.pop();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        rule_input.editing = false;
+        rule_input.error = None;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let entered = match rule_input.prompt {
+            Prompt::Rule => RuleLine::parse(&rule_input.buffer).map(|line| rule_writer.send(ChangeRuleEvent(line))),
+            Prompt::RunTo => match rule_input.buffer.trim().parse() {
+                Ok(generation) => {
+                    run_to_writer.send(RunToEvent(generation));
+                    Ok(())
+                }
+                Err(_) => Err(format!("'{}' is not a generation", rule_input.buffer)),
+            },
+        };
+        match entered {
+            Ok(()) => {
+                rule_input.editing = false;
+                rule_input.error = None;
+            }
+            Err(e) => {
+                rule_input.error = Some(e);
+            }
+        }
+    }
+}
+
+fn rule_text_update_system(
+    rule_input: Res<RuleInput>,
+    active_rule: Res<ActiveRule>,
+    active_noise: Res<ActiveNoise>,
+    (active_topology, board_size): (Res<ActiveTopology>, Res<BoardSize>),
+    stepper: Res<Stepper>,
+    draw_state: Res<DrawState>,
+    mut query: Query<&mut Text, With<RuleText>>,
+) {
+    let changed = rule_input.is_changed()
+        || active_rule.is_changed()
+        || active_noise.is_changed()
+        || active_topology.is_changed()
+        || board_size.is_changed()
+        || stepper.is_changed()
+        || draw_state.is_changed();
+    if !changed {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.sections[1].value = if rule_input.editing && rule_input.prompt == Prompt::Rule {
+            format!("{}_", rule_input.buffer)
+        } else {
+            rule_line(&active_rule.0, &active_noise.noise, active_topology.0, *board_size)
+        };
+        text.sections[3].value = active_noise.seed.to_string();
+        text.sections[5].value = draw_state.0.to_string();
+        text.sections[7].value = active_topology.0.name().to_string();
+        text.sections[9].value = match stepper.engine {
+            Engine::Plain => "Plain".to_string(),
+            Engine::HashLife if Stepper::can_hash(&active_rule.0, &active_noise.noise, active_topology.0) => {
+                format!("HashLife, 2^{} per step", stepper.step)
+            }
+            Engine::HashLife => "HashLife (needs a range 1 rule, no noise, no edges)".to_string(),
+        };
+        text.sections[10].value = match &rule_input.error {
+            Some(e) => format!("  {e}"),
+            None => String::new(),
+        };
+    }
+}
+
+fn speed_slider_system(
+    slider: Query<(&Interaction, &Node, &GlobalTransform), With<SpeedSlider>>,
+    windows: Res<Windows>,
+    mut speed: ResMut<Speed>,
+) {
+    let Ok((interaction, node, transform)) = slider.get_single() else {
+        return;
+    };
+    if *interaction != Interaction::Clicked {
+        return;
+    }
+    let Some(cursor) = windows.get_primary().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+    let left = transform.translation().x - node.size().x / 2.0;
+    let share = ((cursor.x - left) / node.size().x).clamp(0.0, 1.0);
+    speed.steps_per_second = MIN_SPEED * (MAX_SPEED / MIN_SPEED).powf(share);
+}
+
+fn speed_text_update_system(
+    speed: Res<Speed>,
+    rule_input: Res<RuleInput>,
+    mut fill: Query<&mut Style, With<SpeedFill>>,
+    mut query: Query<&mut Text, With<SpeedText>>,
+) {
+    if !speed.is_changed() && !rule_input.is_changed() {
+        return;
+    }
+
+    let share = (speed.steps_per_second / MIN_SPEED).ln() / (MAX_SPEED / MIN_SPEED).ln();
+    for mut style in &mut fill {
+        style.size.width = Val::Percent(share * 100.0);
+    }
+    for mut text in &mut query {
+        text.sections[1].value = format!("{:.0}/s", speed.steps_per_second);
+        text.sections[2].value = match (rule_input.editing && rule_input.prompt == Prompt::RunTo, speed.target) {
+            (true, _) => format!("  Run to: {}_", rule_input.buffer),
+            (false, Some(target)) => format!("  to {target}"),
+            (false, None) => String::new(),
+        };
+    }
+}
+
+fn timeline_slider_system(
+    slider: Query<(&Interaction, &Node, &GlobalTransform), With<TimelineSlider>>,
+    windows: Res<Windows>,
+    board: Res<Board>,
+    mut rewind_writer: EventWriter<RewindEvent>,
+) {
+    let Ok((interaction, node, transform)) = slider.get_single() else {
+        return;
+    };
+    let frames = board.timeline().len();
+    if *interaction != Interaction::Clicked || frames == 0 {
+        return;
+    }
+    let Some(cursor) = windows.get_primary().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+    let left = transform.translation().x - node.size().x / 2.0;
+    let share = ((cursor.x - left) / node.size().x).clamp(0.0, 1.0);
+    let i = (share * (frames - 1) as f32).round() as usize;
+    // holding the button down only rewinds again once the cursor moves on
+    if board.timeline().position() != Some(i) {
+        rewind_writer.send(RewindEvent(i));
+    }
+}
+
+fn timeline_text_update_system(
+    board: Res<Board>,
+    mut fill: Query<&mut Style, With<TimelineFill>>,
+    mut query: Query<&mut Text, With<TimelineText>>,
+) {
+    if !board.is_changed() {
+        return;
+    }
+
+    let timeline = board.timeline();
+    let share = match timeline.position() {
+        Some(i) if timeline.len() > 1 => i as f32 / (timeline.len() - 1) as f32,
+        Some(_) => 0.0,
+        None => 1.0,
+    };
+    for mut style in &mut fill {
+        style.size.width = Val::Percent(share * 100.0);
+    }
+    for mut text in &mut query {
+        text.sections[1].value = match timeline.position() {
+            Some(i) => format!("{} of {}", i + 1, timeline.len()),
+            None => format!("{} back", timeline.len()),
+        };
+    }
+}
+
+fn census_text_update_system(
+    census: Res<ObjectCensus>,
+    mut query: Query<&mut Text, With<CensusText>>,
+) {
+    if !census.is_changed() {
+        return;
+    }
+
+    let Some((generation, census)) = &census.0 else {
+        return;
+    };
+    let mut table = format!("Census at {generation}\n");
+    for entry in census.entries.iter().take(CENSUS_ROWS) {
+        table += &format!("{:>5} {} ({})\n", entry.count, entry.label(), entry.kind);
+    }
+    if census.entries.len() > CENSUS_ROWS {
+        table += &format!("and {} more kinds\n", census.entries.len() - CENSUS_ROWS);
+    }
+    if census.entries.is_empty() {
+        table += "nothing\n";
+    }
+    for mut text in &mut query {
+        text.sections[0].value = table.clone();
+    }
+}
+
 fn fps_update_system(
     diag: Res<Diagnostics>,
     mut query: Query<&mut Text, With<FpsText>>,
@@ -273,10 +1056,18 @@ fn stats_update_system(
     diag: Res<Diagnostics>,
     mut query: Query<(&mut Text), With<StatsText>>,
     camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    board: Res<Board>,
 ) {
     for mut text in &mut query {
         let (trans, cam) = camera.single();
         text.sections[1].value = format!("{:.2}", cam.scale);
         text.sections[3].value = format!("x:{:.2} y:{:.2}", trans.translation.x, trans.translation.y);
+        text.sections[5].value = board.generation().to_string();
+        text.sections[6].value = match board.fate() {
+            Some(Fate::Extinct { generation }) => format!(" died out at {generation}"),
+            Some(Fate::Still { generation }) => format!(" still since {generation}"),
+            Some(Fate::Oscillating { generation, period }) => format!(" period {period} since {generation}"),
+            None => String::new(),
+        };
     }
 }
\ No newline at end of file
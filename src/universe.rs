@@ -0,0 +1,555 @@
+//! An unbounded plane of cells, stored as square chunks that only exist where
+//! something lives.
+
+use std::collections::{HashMap, HashSet};
+use crate::grid::{CellState, Grid, Transition, ALIVE, EMPTY};
+use crate::noise::Rng;
+
+/// Width and height of a chunk, in cells.
+pub const CHUNK_SIZE: i64 = 64;
+
+/// Cells in a chunk.
+pub const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// The cells of one chunk, row by row from its bottom-left corner.
+pub type Chunk = Box<[CellState; CHUNK_AREA]>;
+
+/// A board with no edges, addressed with `i64` coordinates. Chunks of
+/// `CHUNK_SIZE` x `CHUNK_SIZE` cells are allocated when a cell in them is set
+/// and dropped again once they empty, so memory follows the population
+/// rather than the area it spans.
+///
+/// The universe also remembers which chunks changed since the last step, so
+/// the next one can skip everything that is empty or still, and keeps a
+/// Zobrist hash of its cells up to date as they change.
+#[derive(Debug, Clone, Default)]
+pub struct Universe {
+    chunks: HashMap<(i64, i64), ChunkCells>,
+    /// Chunks whose cells changed since the last step began, whether or not
+    /// they still exist.
+    changed: HashSet<(i64, i64)>,
+    /// Every non-empty cell's [`cell_key`] xored together.
+    zobrist: u64,
+}
+
+/// A stored chunk and how many of its cells are not empty, so emptying a
+/// cell can tell whether the chunk went with it without looking at the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChunkCells {
+    cells: Chunk,
+    filled: u16,
+}
+
+impl ChunkCells {
+    fn new(cells: Chunk) -> Self {
+        let filled = cells.iter().filter(|&&c| c != EMPTY).count() as u16;
+        Self { cells, filled }
+    }
+
+    fn empty() -> Self {
+        Self::new(empty_chunk())
+    }
+
+    /// Sets cell `i`, keeping the count.
+    fn set(&mut self, i: usize, state: CellState) {
+        self.filled = self.filled + (state != EMPTY) as u16 - (self.cells[i] != EMPTY) as u16;
+        self.cells[i] = state;
+    }
+}
+
+/// Two universes are equal when they hold the same cells, whatever changed
+/// last.
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        self.chunks == other.chunks
+    }
+}
+
+impl Eq for Universe {}
+
+impl Universe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> CellState {
+        let (chunk, i) = locate(x, y);
+        self.chunks.get(&chunk).map_or(EMPTY, |stored| stored.cells[i])
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, state: CellState) {
+        let (chunk, i) = locate(x, y);
+        let old = self.get(x, y);
+        if old == state {
+            return;
+        }
+        self.changed.insert(chunk);
+        self.zobrist ^= cell_key(x, y, old) ^ cell_key(x, y, state);
+        let stored = self.chunks.entry(chunk).or_insert_with(ChunkCells::empty);
+        stored.set(i, state);
+        if stored.filled == 0 {
+            self.chunks.remove(&chunk);
+        }
+    }
+
+    pub fn is_alive(&self, x: i64, y: i64) -> bool {
+        self.get(x, y) == ALIVE
+    }
+
+    pub fn population(&self) -> usize {
+        self.chunks.values().map(|stored| stored.cells.iter().filter(|&&c| c == ALIVE).count()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// A hash of every cell and its state, equal for universes holding the
+    /// same cells. It is kept as the cells change, so costs nothing to read.
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// Number of chunks currently allocated.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.changed.extend(self.chunks.keys());
+        self.chunks.clear();
+        self.zobrist = 0;
+    }
+
+    /// Empties every cell in a state that a rule with `states` states lacks.
+    pub fn clamp_states(&mut self, states: u8) {
+        for (&chunk, stored) in self.chunks.iter_mut() {
+            for (i, cell) in stored.cells.iter_mut().enumerate().filter(|(_, c)| **c >= states) {
+                let (x, y) = cell_position(chunk, i);
+                self.zobrist ^= cell_key(x, y, *cell);
+                *cell = EMPTY;
+                stored.filled -= 1;
+                self.changed.insert(chunk);
+            }
+        }
+        self.chunks.retain(|_, stored| stored.filled > 0);
+    }
+
+    /// Empties every cell outside the `width` x `height` rectangle whose
+    /// bottom-left cell is `(x0, y0)`.
+    pub fn crop(&mut self, x0: i64, y0: i64, width: i64, height: i64) {
+        let outside: Vec<_> = self
+            .iter()
+            .filter(|&(x, y, _)| x < x0 || x >= x0 + width || y < y0 || y >= y0 + height)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        for (x, y) in outside {
+            self.set(x, y, EMPTY);
+        }
+    }
+
+    /// Iterates over every non-empty cell as `(x, y, state)`, in no
+    /// particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i64, CellState)> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), stored)| {
+            stored
+                .cells
+                .iter()
+                .enumerate()
+                .filter(|(_, &state)| state != EMPTY)
+                .map(move |(i, &state)| {
+                    let (x, y) = cell_position((cx, cy), i);
+                    (x, y, state)
+                })
+        })
+    }
+
+    /// The smallest rectangle holding every non-empty cell, as
+    /// `(min_x, min_y, max_x, max_y)`, or `None` if the universe is empty.
+    pub fn bounds(&self) -> Option<(i64, i64, i64, i64)> {
+        self.iter().fold(None, |bounds, (x, y, _)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+        })
+    }
+
+    /// Counts the non-empty cells of the rectangle from `(x0, y0)` to
+    /// `(x1, y1)` inclusive in aligned blocks `2^level` cells across, calling
+    /// `count` with each block's coordinates (a cell's divided by the block
+    /// size) and its count. Blocks may be reported more than once, with part
+    /// of their count each time. Only allocated chunks are visited, so the
+    /// cost follows the population rather than the size of the rectangle.
+    pub fn filled_blocks(&self, level: u8, (x0, y0, x1, y1): (i64, i64, i64, i64), mut count: impl FnMut(i64, i64, u64)) {
+        for (&(cx, cy), ChunkCells { cells, filled }) in &self.chunks {
+            let (left, bottom) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+            let (xs, ys) = (left.max(x0)..(left + CHUNK_SIZE).min(x1 + 1), bottom.max(y0)..(bottom + CHUNK_SIZE).min(y1 + 1));
+            if xs.is_empty() || ys.is_empty() {
+                continue;
+            }
+            if level as i64 >= CHUNK_SIZE.trailing_zeros() as i64 {
+                // the whole chunk falls in one block, and if it lies wholly
+                // in the rectangle its count is already known
+                let whole = xs.end - xs.start == CHUNK_SIZE && ys.end - ys.start == CHUNK_SIZE;
+                let filled = if whole {
+                    *filled as usize
+                } else {
+                    ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                        .filter(|&(x, y)| cells[((x - left) + (y - bottom) * CHUNK_SIZE) as usize] != EMPTY)
+                        .count()
+                };
+                if filled > 0 {
+                    count(left >> level, bottom >> level, filled as u64);
+                }
+                continue;
+            }
+            for y in ys {
+                let row = &cells[((y - bottom) * CHUNK_SIZE) as usize..][..CHUNK_SIZE as usize];
+                for x in xs.clone().filter(|&x| row[(x - left) as usize] != EMPTY) {
+                    count(x >> level, y >> level, 1);
+                }
+            }
+        }
+    }
+
+    /// A copy of the `width` x `height` rectangle whose bottom-left cell is
+    /// `(x0, y0)`, as a grid with dead edges.
+    pub fn window(&self, x0: i64, y0: i64, width: i32, height: i32) -> Grid {
+        let mut grid = Grid::new(width, height);
+        let (x1, y1) = (x0 + width as i64, y0 + height as i64);
+        for cy in y0.div_euclid(CHUNK_SIZE)..=(y1 - 1).div_euclid(CHUNK_SIZE) {
+            for cx in x0.div_euclid(CHUNK_SIZE)..=(x1 - 1).div_euclid(CHUNK_SIZE) {
+                let Some(ChunkCells { cells, .. }) = self.chunks.get(&(cx, cy)) else {
+                    continue;
+                };
+                let (left, bottom) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+                for y in bottom.max(y0)..(bottom + CHUNK_SIZE).min(y1) {
+                    for x in left.max(x0)..(left + CHUNK_SIZE).min(x1) {
+                        let state = cells[((x - left) + (y - bottom) * CHUNK_SIZE) as usize];
+                        if state != EMPTY {
+                            grid.set((x - x0) as i32, (y - y0) as i32, state);
+                        }
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Copies every cell of `grid` into the universe with the grid's
+    /// bottom-left cell at `(x0, y0)`, empty cells included.
+    pub fn paste(&mut self, grid: &Grid, x0: i64, y0: i64) {
+        for (x, y, state) in grid.iter() {
+            let (x, y) = (x0 + x as i64, y0 + y as i64);
+            let (chunk, i) = locate(x, y);
+            let stored = match self.chunks.get_mut(&chunk) {
+                Some(stored) => stored,
+                None if state == EMPTY => continue,
+                None => self.chunks.entry(chunk).or_insert_with(ChunkCells::empty),
+            };
+            let old = stored.cells[i];
+            if old != state {
+                self.zobrist ^= cell_key(x, y, old) ^ cell_key(x, y, state);
+                stored.set(i, state);
+                self.changed.insert(chunk);
+            }
+        }
+        self.chunks.retain(|_, stored| stored.filled > 0);
+    }
+
+    /// Advances the universe by one generation of `rule`, which must only
+    /// look `reach` cells away; see [`Universe::step_with`].
+    pub fn step(&mut self, rule: &impl Transition, reach: u32) {
+        self.step_with(reach, |grid, _| rule.next_states(grid));
+    }
+
+    /// Advances the universe by one generation. Every chunk that changed
+    /// since the last step, or lies within `reach` cells of one that did, is
+    /// handed to `next_states` as a grid holding the chunk and a margin of
+    /// `reach` cells around it, along with the chunk's coordinates. The chunk
+    /// takes the middle of the result.
+    ///
+    /// Chunks whose surroundings stood still would come out as they are, so
+    /// they are skipped; a rule that can change them anyway, such as a random
+    /// one, needs [`Universe::touch_all`] first. Only cells near live ones are
+    /// ever visited, so rules where empty neighbourhoods give birth (B0) only
+    /// act around the population, and only where a window holds something.
+    ///
+    /// The same step can be spread over threads with
+    /// [`Universe::active_chunks`], [`Universe::next_chunk`] and
+    /// [`Universe::update_chunks`].
+    pub fn step_with(&mut self, reach: u32, next_states: impl Fn(&Grid, (i64, i64)) -> Vec<CellState>) {
+        let next: Vec<_> = self
+            .active_chunks(reach)
+            .into_iter()
+            .map(|chunk| (chunk, self.next_chunk(chunk, reach, &next_states)))
+            .collect();
+        self.update_chunks(next);
+    }
+
+    /// Every chunk a step with the given reach has to visit, sorted by row
+    /// and then column, so runs of equal `y` form horizontal bands.
+    pub fn active_chunks(&self, reach: u32) -> Vec<(i64, i64)> {
+        let spread = (reach as i64 + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let mut chunks: Vec<(i64, i64)> = self
+            .changed
+            .iter()
+            .flat_map(|&(cx, cy)| {
+                (-spread..=spread).flat_map(move |dy| (-spread..=spread).map(move |dx| (cx + dx, cy + dy)))
+            })
+            .collect();
+        chunks.sort_unstable_by_key(|&(cx, cy)| (cy, cx));
+        chunks.dedup();
+        chunks
+    }
+
+    /// The next generation of one chunk, as in [`Universe::step_with`], or
+    /// `None` if it comes out empty.
+    pub fn next_chunk(
+        &self,
+        (cx, cy): (i64, i64),
+        reach: u32,
+        next_states: impl Fn(&Grid, (i64, i64)) -> Vec<CellState>,
+    ) -> Option<Chunk> {
+        let reach = reach as i64;
+        let side = (CHUNK_SIZE + 2 * reach) as i32;
+        let window = self.window(cx * CHUNK_SIZE - reach, cy * CHUNK_SIZE - reach, side, side);
+        if window.iter().all(|(_, _, state)| state == EMPTY) {
+            return None;
+        }
+        let states = next_states(&window, (cx, cy));
+        let mut cells = empty_chunk();
+        for y in 0..CHUNK_SIZE {
+            let row = ((y + reach) * side as i64 + reach) as usize;
+            let start = (y * CHUNK_SIZE) as usize;
+            cells[start..start + CHUNK_SIZE as usize].copy_from_slice(&states[row..row + CHUNK_SIZE as usize]);
+        }
+        cells.iter().any(|&c| c != EMPTY).then_some(cells)
+    }
+
+    /// Finishes a step: puts the results of [`Universe::next_chunk`] in
+    /// place, `None` emptying the chunk, and records which of them changed.
+    /// Chunks left out keep their cells.
+    pub fn update_chunks(&mut self, next: impl IntoIterator<Item = ((i64, i64), Option<Chunk>)>) {
+        self.changed.clear();
+        for (chunk, cells) in next {
+            let changed = match cells {
+                Some(cells) => match self.chunks.get_mut(&chunk) {
+                    Some(old) if old.cells == cells => false,
+                    Some(old) => {
+                        self.zobrist ^= chunk_key(chunk, &old.cells) ^ chunk_key(chunk, &cells);
+                        *old = ChunkCells::new(cells);
+                        true
+                    }
+                    None => {
+                        self.zobrist ^= chunk_key(chunk, &cells);
+                        self.chunks.insert(chunk, ChunkCells::new(cells));
+                        true
+                    }
+                },
+                None => match self.chunks.remove(&chunk) {
+                    Some(old) => {
+                        self.zobrist ^= chunk_key(chunk, &old.cells);
+                        true
+                    }
+                    None => false,
+                },
+            };
+            if changed {
+                self.changed.insert(chunk);
+            }
+        }
+    }
+
+    /// Chunks whose cells changed since the last step began.
+    pub fn changed_chunks(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.changed.iter().copied()
+    }
+
+    /// Marks every chunk as changed, so the next step visits them all.
+    pub fn touch_all(&mut self) {
+        self.changed.extend(self.chunks.keys());
+    }
+
+    /// Forgets which chunks changed, for a step made by editing the cells
+    /// directly, such as pasting a stepped window back.
+    pub fn forget_changes(&mut self) {
+        self.changed.clear();
+    }
+}
+
+/// The chunk holding `(x, y)`.
+pub fn chunk_of(x: i64, y: i64) -> (i64, i64) {
+    (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+}
+
+/// The chunk holding `(x, y)` and the cell's index within it.
+fn locate(x: i64, y: i64) -> ((i64, i64), usize) {
+    let chunk = chunk_of(x, y);
+    let i = x.rem_euclid(CHUNK_SIZE) + y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE;
+    (chunk, i as usize)
+}
+
+/// The cell at index `i` of a chunk.
+fn cell_position((cx, cy): (i64, i64), i: usize) -> (i64, i64) {
+    (cx * CHUNK_SIZE + i as i64 % CHUNK_SIZE, cy * CHUNK_SIZE + i as i64 / CHUNK_SIZE)
+}
+
+/// A random number standing for a cell in a state, zero for an empty one,
+/// so a board's Zobrist hash is the xor of its cells' keys.
+fn cell_key(x: i64, y: i64, state: CellState) -> u64 {
+    if state == EMPTY {
+        0
+    } else {
+        Rng::region_seed(state as u64, (x, y))
+    }
+}
+
+/// The xor of the keys of a chunk's cells.
+fn chunk_key(chunk: (i64, i64), cells: &[CellState; CHUNK_AREA]) -> u64 {
+    cells
+        .iter()
+        .enumerate()
+        .filter(|(_, &state)| state != EMPTY)
+        .fold(0, |key, (i, &state)| {
+            let (x, y) = cell_position(chunk, i);
+            key ^ cell_key(x, y, state)
+        })
+}
+
+fn empty_chunk() -> Chunk {
+    Box::new([EMPTY; CHUNK_AREA])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    fn place(universe: &mut Universe, cells: &[(i64, i64)], x0: i64, y0: i64) {
+        for &(x, y) in cells {
+            universe.set(x0 + x, y0 + y, ALIVE);
+        }
+    }
+
+    const GLIDER: [(i64, i64); 5] = [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)];
+    const R_PENTOMINO: [(i64, i64); 5] = [(1, 2), (2, 2), (0, 1), (1, 1), (1, 0)];
+
+    #[test]
+    fn chunks_come_and_go_with_their_cells() {
+        let mut universe = Universe::new();
+        universe.set(-1, -1, ALIVE);
+        universe.set(64, 0, ALIVE);
+        universe.set(65, 1, 2);
+        assert_eq!(universe.chunk_count(), 2);
+        assert_eq!((universe.get(-1, -1), universe.get(65, 1), universe.get(0, 0)), (ALIVE, 2, EMPTY));
+        assert_eq!(universe.population(), 2);
+        assert_eq!(universe.bounds(), Some((-1, -1, 65, 1)));
+        universe.set(-1, -1, EMPTY);
+        assert_eq!(universe.chunk_count(), 1);
+        universe.crop(0, 0, 65, 65);
+        assert_eq!(universe.iter().collect::<Vec<_>>(), [(64, 0, ALIVE)]);
+        universe.clear();
+        assert!(universe.is_empty() && universe.bounds().is_none());
+    }
+
+    #[test]
+    fn windows_and_pastes_round_trip() {
+        let mut universe = Universe::new();
+        place(&mut universe, &R_PENTOMINO, 62, -2);
+        let window = universe.window(60, -4, 8, 8);
+        assert_eq!(window.population(), 5);
+        assert!(window.is_alive(3, 4));
+        let mut copy = Universe::new();
+        copy.set(61, -3, ALIVE);
+        copy.paste(&window, 60, -4);
+        assert_eq!(copy, universe);
+        assert_eq!(copy.zobrist(), universe.zobrist());
+    }
+
+    #[test]
+    fn gliders_cross_chunks_in_every_direction() {
+        let mut universe = Universe::new();
+        place(&mut universe, &GLIDER, 60, 3);
+        for _ in 0..4 * 70 {
+            universe.step(&Rule::life(), 1);
+        }
+        let mut expected = Universe::new();
+        place(&mut expected, &GLIDER, 130, -67);
+        assert_eq!(universe, expected);
+        assert_eq!(universe.chunk_count(), 1);
+    }
+
+    #[test]
+    fn stepping_agrees_with_a_large_grid() {
+        let mut universe = Universe::new();
+        place(&mut universe, &R_PENTOMINO, -1, -1);
+        let mut grid = universe.window(-150, -150, 300, 300);
+        for _ in 0..100 {
+            universe.step(&Rule::life(), 1);
+            grid.step(&Rule::life());
+        }
+        assert_eq!(universe.window(-150, -150, 300, 300), grid);
+        assert_eq!(universe.population(), grid.population());
+    }
+
+    #[test]
+    fn still_chunks_are_skipped() {
+        let mut universe = Universe::new();
+        place(&mut universe, &[(0, 0), (1, 0), (0, 1), (1, 1)], 10, 10);
+        place(&mut universe, &[(0, 0), (1, 0), (2, 0)], 1000, 1000);
+        universe.step(&Rule::life(), 1);
+        assert_eq!(universe.changed_chunks().collect::<Vec<_>>(), [chunk_of(1001, 1000)]);
+        assert!(!universe.active_chunks(1).contains(&chunk_of(10, 10)));
+        universe.touch_all();
+        assert!(universe.active_chunks(1).contains(&chunk_of(10, 10)));
+    }
+
+    #[test]
+    fn zobrist_hash_follows_the_cells() {
+        let mut stepped = Universe::new();
+        place(&mut stepped, &R_PENTOMINO, 63, 63);
+        for _ in 0..30 {
+            stepped.step(&Rule::life(), 1);
+        }
+        let mut rebuilt = Universe::new();
+        for (x, y, state) in stepped.iter() {
+            rebuilt.set(x, y, state);
+        }
+        assert_eq!(stepped.zobrist(), rebuilt.zobrist());
+        rebuilt.set(0, 500, ALIVE);
+        assert_ne!(stepped.zobrist(), rebuilt.zobrist());
+        rebuilt.set(0, 500, EMPTY);
+        assert_eq!(stepped.zobrist(), rebuilt.zobrist());
+        rebuilt.clear();
+        assert_eq!(rebuilt.zobrist(), Universe::new().zobrist());
+    }
+
+    #[test]
+    fn chunks_keep_count_of_their_cells() {
+        let counted = |universe: &Universe| {
+            universe.chunks.values().all(|stored| stored.filled as usize == stored.cells.iter().filter(|&&c| c != EMPTY).count())
+        };
+        let mut universe = Universe::new();
+        for i in 0..300 {
+            universe.set(i * 7 % 200 - 100, i * 13 % 150 - 75, 1 + (i % 3) as CellState);
+        }
+        universe.set(5, 5, ALIVE);
+        universe.set(5, 5, 2);
+        assert!(counted(&universe));
+
+        let mut grid = Grid::new(80, 3);
+        grid.set(79, 2, ALIVE);
+        universe.paste(&grid, -40, 0);
+        assert!(counted(&universe));
+        universe.clamp_states(2);
+        assert!(counted(&universe));
+
+        // cropping empties whole chunks, which go as their last cell does
+        universe.crop(0, 0, 64, 64);
+        assert!(counted(&universe));
+        assert_eq!(universe.chunk_count(), 1);
+        universe.crop(0, 0, 0, 0);
+        assert!(universe.is_empty());
+        assert_eq!(universe.zobrist(), 0);
+    }
+}